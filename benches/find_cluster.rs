@@ -0,0 +1,58 @@
+use ball_shooter::{grid, hex};
+use bevy::prelude::Entity;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A full 16x16 board of four colors, the size of a new game.
+fn full_board() -> grid::Grid {
+    let mut grid = grid::Grid::default();
+    let mut id = 0;
+    for r in 0..16 {
+        for q in -r / 2..16 - r / 2 {
+            grid.set(hex::Coord::new(q, r), Some(Entity::from_raw(id)));
+            id += 1;
+        }
+    }
+    grid
+}
+
+fn color(entity: &Entity) -> u32 {
+    // scrambled so clusters vary in size
+    entity.id().wrapping_mul(2654435761) >> 30
+}
+
+fn bench_find_cluster(c: &mut Criterion) {
+    let grid = full_board();
+    let origins = grid.storage.keys().copied().collect::<Vec<_>>();
+
+    c.bench_function("find_cluster", |b| {
+        b.iter(|| {
+            for &origin in origins.iter() {
+                let species = color(grid.get(origin).unwrap());
+                black_box(grid::find_cluster(&grid, origin, |e| color(e) == species));
+            }
+        })
+    });
+
+    c.bench_function("find_cluster_with scratch", |b| {
+        let mut scratch = grid::ClusterScratch::default();
+        b.iter(|| {
+            for &origin in origins.iter() {
+                let species = color(grid.get(origin).unwrap());
+                black_box(grid::find_cluster_with(
+                    &grid,
+                    origin,
+                    |e| color(e) == species,
+                    &mut scratch,
+                ));
+            }
+        })
+    });
+
+    c.bench_function("find_floating_clusters", |b| {
+        let mut scratch = grid::ClusterScratch::default();
+        b.iter(|| black_box(grid::find_floating_clusters(&grid, &mut scratch)))
+    });
+}
+
+criterion_group!(benches, bench_find_cluster);
+criterion_main!(benches);
@@ -1,5 +1,7 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
 
 use crate::loading::TextureAssets;
 
@@ -10,7 +12,31 @@ pub const BALL_RADIUS_COEFF: f32 = hex::INNER_RADIUS_COEFF * 0.85;
 #[derive(Component)]
 pub struct Ball;
 
-#[derive(Component, Copy, Clone, PartialEq, Debug)]
+/// Marks an immovable ball without a [Species], it never matches or drops.
+#[derive(Component)]
+pub struct Obstacle;
+
+pub const OBSTACLE_COLOR: Color = Color::DARK_GRAY;
+
+/// Clears an armored ball absorbs, including the one removing it. Balls without it are
+/// removed by a single clear. See [crate::grid::Grid::health].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Health(pub u32);
+
+/// Color of balls hidden by [crate::grid::FogOfWar].
+pub const FOG_COLOR: Color = Color::GRAY;
+
+pub const ARMOR_COLOR: Color = Color::rgb(0.75, 0.9, 1.0);
+
+/// Returns the color of a ball, tinted toward [ARMOR_COLOR] the more health it has left.
+pub fn health_color(species: Species, health: u32) -> Color {
+    let t = 1.0 - 1.0 / health.max(1) as f32;
+    let from = Vec4::from(species_to_color(species).as_rgba_f32());
+    let to = Vec4::from(ARMOR_COLOR.as_rgba_f32());
+    from.lerp(to, t).into()
+}
+
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum Species {
     Red,
     Blue,
@@ -19,6 +45,22 @@ pub enum Species {
     White,
 }
 
+impl Species {
+    /// Number of species.
+    pub const COUNT: usize = 5;
+
+    /// Returns every species.
+    pub fn all() -> &'static [Species; Species::COUNT] {
+        &[
+            Species::Red,
+            Species::Blue,
+            Species::Green,
+            Species::Yellow,
+            Species::White,
+        ]
+    }
+}
+
 pub fn species_to_color(species: Species) -> Color {
     match species {
         Species::Red => Color::rgb_u8(244, 47, 47),
@@ -30,13 +72,121 @@ pub fn species_to_color(species: Species) -> Color {
 }
 
 pub fn random_species() -> Species {
-    match rand::random::<u8>() % 5 {
-        0 => Species::Red,
-        1 => Species::Blue,
-        2 => Species::Green,
-        3 => Species::Yellow,
-        4 => Species::White,
-        _ => unreachable!(),
+    Species::all()[rand::random::<usize>() % Species::COUNT]
+}
+
+/// Ball meshes, created on first use and shared by every ball of the same shape and radius.
+#[derive(Debug, Default)]
+pub struct SpeciesMesh {
+    /// Give each species its own shape to help telling them apart, colliders stay spheres.
+    pub distinct: bool,
+    cache: HashMap<(Option<Species>, u32), Handle<Mesh>>,
+}
+
+impl SpeciesMesh {
+    /// Returns the mesh of a ball with the given species and radius.
+    pub fn get(
+        &mut self,
+        species: Species,
+        radius: f32,
+        meshes: &mut Assets<Mesh>,
+    ) -> Handle<Mesh> {
+        let distinct = self.distinct;
+        let key = (distinct.then(|| species), radius.to_bits());
+        self.cache
+            .entry(key)
+            .or_insert_with(|| {
+                let mesh = match distinct {
+                    true => species_mesh(species, radius),
+                    false => Mesh::from(shape::Icosphere {
+                        subdivisions: 1,
+                        radius,
+                    }),
+                };
+                meshes.add(mesh)
+            })
+            .clone()
+    }
+}
+
+fn species_mesh(species: Species, radius: f32) -> Mesh {
+    match species {
+        Species::Red => Mesh::from(shape::Icosphere {
+            subdivisions: 1,
+            radius,
+        }),
+        Species::Blue => Mesh::from(shape::Cube { size: radius * 1.6 }),
+        Species::Green => Mesh::from(shape::Capsule {
+            radius: radius * 0.6,
+            depth: radius * 0.8,
+            ..default()
+        }),
+        Species::Yellow => Mesh::from(shape::Torus {
+            radius: radius * 0.7,
+            ring_radius: radius * 0.3,
+            ..default()
+        }),
+        Species::White => Mesh::from(shape::Icosphere {
+            subdivisions: 0,
+            radius,
+        }),
+    }
+}
+
+/// Ball materials, one per color, created on first use and shared by every ball showing that
+/// color. Recolor a ball by swapping its handle, never by editing the shared material.
+#[derive(Debug, Default)]
+pub struct BallMaterials {
+    cache: HashMap<[u32; 4], Handle<StandardMaterial>>,
+}
+
+impl BallMaterials {
+    /// Returns the material of a ball with the given color.
+    pub fn get(
+        &mut self,
+        color: Color,
+        textures: &TextureAssets,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Handle<StandardMaterial> {
+        let key = color.as_rgba_f32().map(f32::to_bits);
+        self.cache
+            .entry(key)
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: color,
+                    base_color_texture: Some(textures.texture_bevy.clone()),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                })
+            })
+            .clone()
+    }
+}
+
+/// Assets used when spawning balls and projectiles.
+#[derive(SystemParam)]
+pub struct BallAssets<'w, 's> {
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+    pub materials: ResMut<'w, Assets<StandardMaterial>>,
+    pub textures: Res<'w, TextureAssets>,
+    pub species_mesh: ResMut<'w, SpeciesMesh>,
+    pub material_cache: ResMut<'w, BallMaterials>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> BallAssets<'w, 's> {
+    /// Returns the shared mesh of a ball of the given species and hex radius.
+    pub fn mesh(&mut self, species: Species, radius: f32) -> Handle<Mesh> {
+        self.species_mesh
+            .get(species, radius * BALL_RADIUS_COEFF, &mut self.meshes)
+    }
+
+    /// Returns the shared material of a ball with the given color.
+    pub fn material(&mut self, color: Color) -> Handle<StandardMaterial> {
+        self.material_cache
+            .get(color, &self.textures, &mut self.materials)
     }
 }
 
@@ -51,13 +201,50 @@ pub struct BallBundle {
 }
 
 impl BallBundle {
+    pub fn new(pos: Vec3, radius: f32, species: Species, assets: &mut BallAssets) -> Self {
+        Self {
+            pbr: PbrBundle {
+                mesh: assets.mesh(species, radius),
+                material: assets.material(species_to_color(species)),
+                transform: Transform::from_translation(pos),
+                ..Default::default()
+            },
+            collider: Collider::ball(radius * BALL_RADIUS_COEFF),
+            species: species,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for BallBundle {
+    fn default() -> Self {
+        BallBundle {
+            pbr: Default::default(),
+            ball: Ball,
+            collider: Collider::ball(1.),
+            collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
+            species: Species::Red,
+        }
+    }
+}
+
+/// An [Obstacle] cell, blocks projectiles like any other ball.
+#[derive(Bundle)]
+pub struct ObstacleBundle {
+    #[bundle]
+    pub pbr: PbrBundle,
+    pub ball: Ball,
+    pub obstacle: Obstacle,
+    pub collider: Collider,
+    pub collision_types: ActiveCollisionTypes,
+}
+
+impl ObstacleBundle {
     pub fn new(
         pos: Vec3,
         radius: f32,
-        species: Species,
         meshes: &mut ResMut<Assets<Mesh>>,
         materials: &mut ResMut<Assets<StandardMaterial>>,
-        texture_assets: &Res<TextureAssets>,
     ) -> Self {
         Self {
             pbr: PbrBundle {
@@ -66,30 +253,92 @@ impl BallBundle {
                     radius: radius * BALL_RADIUS_COEFF,
                 })),
                 material: materials.add(StandardMaterial {
-                    base_color: species_to_color(species).into(),
-                    base_color_texture: Some(texture_assets.texture_bevy.clone()),
-                    alpha_mode: AlphaMode::Blend,
+                    base_color: OBSTACLE_COLOR,
                     unlit: true,
                     ..default()
                 }),
                 transform: Transform::from_translation(pos),
                 ..Default::default()
             },
+            ball: Ball,
+            obstacle: Obstacle,
             collider: Collider::ball(radius * BALL_RADIUS_COEFF),
-            species: species,
-            ..Default::default()
+            collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
         }
     }
 }
 
-impl Default for BallBundle {
-    fn default() -> Self {
-        BallBundle {
-            pbr: Default::default(),
-            ball: Ball,
-            collider: Collider::ball(1.),
-            collision_types: ActiveCollisionTypes::KINEMATIC_STATIC,
-            species: Species::Red,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn all_species_are_listed_once() {
+        assert_eq!(Species::all().len(), Species::COUNT);
+        let unique = Species::all().iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), Species::COUNT);
+    }
+
+    fn asset_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>();
+        app
+    }
+
+    #[test]
+    fn repeated_spawns_share_one_mesh() {
+        let mut app = asset_app();
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let mut cache = SpeciesMesh::default();
+
+        let first = cache.get(Species::Red, 0.5, &mut meshes);
+        for species in Species::all().iter().cycle().take(50) {
+            assert_eq!(cache.get(*species, 0.5, &mut meshes), first);
+        }
+        assert_eq!(meshes.len(), 1);
+
+        cache.get(Species::Red, 0.25, &mut meshes);
+        assert_eq!(meshes.len(), 2);
+    }
+
+    #[test]
+    fn distinct_meshes_are_made_once_per_species() {
+        let mut app = asset_app();
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let mut cache = SpeciesMesh {
+            distinct: true,
+            ..default()
+        };
+
+        for species in Species::all().iter().cycle().take(50) {
+            cache.get(*species, 0.5, &mut meshes);
+        }
+        assert_eq!(meshes.len(), Species::COUNT);
+    }
+
+    #[test]
+    fn repeated_spawns_share_one_material_per_color() {
+        let mut app = asset_app();
+        let mut materials = app.world.resource_mut::<Assets<StandardMaterial>>();
+        let textures = TextureAssets {
+            texture_bevy: Handle::default(),
+        };
+        let mut cache = BallMaterials::default();
+
+        for species in Species::all().iter().cycle().take(50) {
+            cache.get(species_to_color(*species), &textures, &mut materials);
         }
+        assert_eq!(materials.len(), Species::COUNT);
+
+        let red = cache.get(species_to_color(Species::Red), &textures, &mut materials);
+        assert_eq!(
+            red,
+            cache.get(species_to_color(Species::Red), &textures, &mut materials)
+        );
+        assert_eq!(materials.len(), Species::COUNT);
     }
 }
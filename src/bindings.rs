@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const BINDINGS_KEY: &str = "ball_shooter_bindings";
+
+/// Keys the menus navigate with, see [crate::navigation::nav_input], and Escape, which cancels
+/// rebinding. They can't be bound to actions.
+pub const RESERVED_KEYS: [KeyCode; 4] =
+    [KeyCode::Up, KeyCode::Down, KeyCode::Return, KeyCode::Escape];
+
+/// A key or mouse button bound to an action.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    pub fn pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+        }
+    }
+
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            Binding::Key(key) => keys.just_pressed(key),
+            Binding::Mouse(button) => mouse.just_pressed(button),
+        }
+    }
+
+    pub fn just_released(&self, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        match *self {
+            Binding::Key(key) => keys.just_released(key),
+            Binding::Mouse(button) => mouse.just_released(button),
+        }
+    }
+}
+
+/// Returns true if `binding` can be used for `action`, only fire takes mouse buttons.
+fn accepts(action: Action, binding: Binding) -> bool {
+    action == Action::Fire || matches!(binding, Binding::Key(_))
+}
+
+/// Inputs bound to each action, persisted between sessions.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub fire: Binding,
+    /// Resets the board in [crate::gameplay::GameMode::Practice].
+    pub restart: KeyCode,
+    pub toggle_aim_guides: KeyCode,
+    pub toggle_slow_motion: KeyCode,
+    pub toggle_pause: KeyCode,
+    pub replay_tutorial: KeyCode,
+}
+
+/// An action that can be rebound from the controls menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Fire,
+    Restart,
+    ToggleAimGuides,
+    ToggleSlowMotion,
+    TogglePause,
+    ReplayTutorial,
+}
+
+impl Action {
+    /// Returns every action, in the order they're listed in the controls menu.
+    pub fn all() -> &'static [Action] {
+        &[
+            Action::Fire,
+            Action::Restart,
+            Action::ToggleAimGuides,
+            Action::ToggleSlowMotion,
+            Action::TogglePause,
+            Action::ReplayTutorial,
+        ]
+    }
+
+    /// Returns the localization key of the action name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::Fire => "action_fire",
+            Action::Restart => "action_restart",
+            Action::ToggleAimGuides => "action_aim_guides",
+            Action::ToggleSlowMotion => "action_slow_motion",
+            Action::TogglePause => "action_pause",
+            Action::ReplayTutorial => "action_tutorial",
+        }
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(button) => write!(f, "Mouse {:?}", button),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Returns the input bound to `action`.
+    pub fn get(&self, action: Action) -> Binding {
+        match action {
+            Action::Fire => self.fire,
+            Action::Restart => Binding::Key(self.restart),
+            Action::ToggleAimGuides => Binding::Key(self.toggle_aim_guides),
+            Action::ToggleSlowMotion => Binding::Key(self.toggle_slow_motion),
+            Action::TogglePause => Binding::Key(self.toggle_pause),
+            Action::ReplayTutorial => Binding::Key(self.replay_tutorial),
+        }
+    }
+
+    /// Binds `action` to `binding`. An action already bound to `binding` swaps over to the
+    /// previous binding of `action`. Returns false, leaving every binding as it was, if
+    /// `binding` is one of the [RESERVED_KEYS] or either action can't use its new binding.
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> bool {
+        if matches!(binding, Binding::Key(key) if RESERVED_KEYS.contains(&key)) {
+            return false;
+        }
+        if !accepts(action, binding) {
+            return false;
+        }
+
+        let previous = self.get(action);
+        let taken = Action::all()
+            .iter()
+            .copied()
+            .find(|&other| other != action && self.get(other) == binding);
+        if let Some(other) = taken {
+            if !accepts(other, previous) {
+                return false;
+            }
+            self.set(other, previous);
+        }
+        self.set(action, binding);
+        true
+    }
+
+    fn set(&mut self, action: Action, binding: Binding) {
+        match (action, binding) {
+            (Action::Fire, binding) => self.fire = binding,
+            (_, Binding::Mouse(_)) => {}
+            (Action::Restart, Binding::Key(key)) => self.restart = key,
+            (Action::ToggleAimGuides, Binding::Key(key)) => self.toggle_aim_guides = key,
+            (Action::ToggleSlowMotion, Binding::Key(key)) => self.toggle_slow_motion = key,
+            (Action::TogglePause, Binding::Key(key)) => self.toggle_pause = key,
+            (Action::ReplayTutorial, Binding::Key(key)) => self.replay_tutorial = key,
+        }
+    }
+
+    /// Load the saved bindings, defaults if none were saved.
+    pub fn load() -> Self {
+        storage::load(BINDINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(BINDINGS_KEY, self);
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            fire: Binding::Mouse(MouseButton::Left),
+            restart: KeyCode::R,
+            toggle_aim_guides: KeyCode::G,
+            toggle_slow_motion: KeyCode::T,
+            toggle_pause: KeyCode::P,
+            replay_tutorial: KeyCode::H,
+        }
+    }
+}
+
+fn load_bindings(mut commands: Commands) {
+    let bindings = KeyBindings::load();
+    // written back so every action shows up in the saved bindings
+    bindings.save();
+    commands.insert_resource(bindings);
+}
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_bindings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind_keys() {
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.rebind(Action::TogglePause, Binding::Key(KeyCode::Space)));
+        assert_eq!(bindings.toggle_pause, KeyCode::Space);
+        assert_eq!(
+            bindings.get(Action::TogglePause),
+            Binding::Key(KeyCode::Space)
+        );
+    }
+
+    #[test]
+    fn only_fire_takes_mouse_buttons() {
+        let mut bindings = KeyBindings::default();
+        assert!(!bindings.rebind(Action::Restart, Binding::Mouse(MouseButton::Right)));
+        assert_eq!(bindings.restart, KeyCode::R);
+
+        assert!(bindings.rebind(Action::Fire, Binding::Key(KeyCode::Space)));
+        assert_eq!(bindings.fire, Binding::Key(KeyCode::Space));
+        assert!(bindings.rebind(Action::Fire, Binding::Mouse(MouseButton::Right)));
+        assert_eq!(bindings.fire, Binding::Mouse(MouseButton::Right));
+    }
+
+    #[test]
+    fn bound_keys_swap_with_the_rebound_action() {
+        let mut bindings = KeyBindings::default();
+        assert!(bindings.rebind(Action::Restart, Binding::Key(KeyCode::P)));
+        assert_eq!(bindings.restart, KeyCode::P);
+        assert_eq!(bindings.toggle_pause, KeyCode::R);
+
+        // rebinding to the current key changes nothing
+        assert!(bindings.rebind(Action::Restart, Binding::Key(KeyCode::P)));
+        assert_eq!(bindings.restart, KeyCode::P);
+        assert_eq!(bindings.toggle_pause, KeyCode::R);
+    }
+
+    #[test]
+    fn swaps_that_would_bind_a_mouse_button_to_a_key_action_are_refused() {
+        let mut bindings = KeyBindings::default();
+        // restart would end up on the left mouse button
+        assert!(!bindings.rebind(Action::Fire, Binding::Key(KeyCode::R)));
+        assert_eq!(bindings.fire, Binding::Mouse(MouseButton::Left));
+        assert_eq!(bindings.restart, KeyCode::R);
+
+        assert!(bindings.rebind(Action::Fire, Binding::Key(KeyCode::Space)));
+        assert!(bindings.rebind(Action::Fire, Binding::Key(KeyCode::R)));
+        assert_eq!(bindings.fire, Binding::Key(KeyCode::R));
+        assert_eq!(bindings.restart, KeyCode::Space);
+    }
+
+    #[test]
+    fn menu_keys_are_reserved() {
+        let mut bindings = KeyBindings::default();
+        for key in RESERVED_KEYS {
+            assert!(!bindings.rebind(Action::Fire, Binding::Key(key)));
+            assert!(!bindings.rebind(Action::TogglePause, Binding::Key(key)));
+        }
+        assert_eq!(bindings.fire, Binding::Mouse(MouseButton::Left));
+        assert_eq!(bindings.toggle_pause, KeyCode::P);
+    }
+}
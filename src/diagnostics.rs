@@ -3,6 +3,14 @@ use bevy::prelude::*;
 use bevy_egui::*;
 use bevy_inspector_egui::WorldInspectorPlugin;
 
+use crate::{
+    ball::{Ball, Species},
+    gameplay::MainCamera,
+    grid::Grid,
+    projectile::ProjectileBuffer,
+    utils,
+};
+
 pub fn diagnostic_ui(ui: &mut egui::Ui, diagnostics: &Diagnostics) {
     egui::Grid::new("frame time diagnostics").show(ui, |ui| {
         for diagnostic in diagnostics.iter() {
@@ -15,18 +23,90 @@ pub fn diagnostic_ui(ui: &mut egui::Ui, diagnostics: &Diagnostics) {
     });
 }
 
+/// Shows the number of balls of each species on the board and in the upcoming buffer.
+pub fn species_ui<'a>(
+    ui: &mut egui::Ui,
+    board: impl Iterator<Item = &'a Species>,
+    buffer: &ProjectileBuffer,
+) {
+    let mut counts = [(0, 0); Species::COUNT];
+    let index = |species: &Species| Species::all().iter().position(|s| s == species).unwrap();
+    for species in board {
+        counts[index(species)].0 += 1;
+    }
+    for species in buffer.queue.iter() {
+        counts[index(species)].1 += 1;
+    }
+
+    egui::Grid::new("species diagnostics").show(ui, |ui| {
+        ui.label("species");
+        ui.label("board");
+        ui.label("buffer");
+        ui.end_row();
+        for (species, (board, buffer)) in Species::all().iter().zip(counts) {
+            ui.label(format!("{:?}", species));
+            ui.label(board.to_string());
+            ui.label(buffer.to_string());
+            ui.end_row();
+        }
+    });
+}
+
 pub fn egui_display_diagnostics(
     mut egui_context: ResMut<EguiContext>,
     diagnostics: Res<Diagnostics>,
+    balls: Query<&Species, With<Ball>>,
+    buffer: Res<ProjectileBuffer>,
 ) {
     egui::Window::new("diagnostics")
         .min_width(0.0)
         .default_width(1.0)
         .show(egui_context.ctx_mut(), |ui| {
             diagnostic_ui(ui, &*diagnostics);
+            ui.separator();
+            species_ui(ui, balls.iter(), &*buffer);
         });
 }
 
+/// Shows the entity, coordinate and species of the ball under the cursor in a tooltip.
+pub fn egui_inspect_hovered_ball(
+    mut egui_context: ResMut<EguiContext>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    grid: Res<Grid>,
+    balls: Query<Option<&Species>, With<Ball>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) if window.cursor_position().is_some() => window,
+        _ => return,
+    };
+    let (camera, camera_transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    let (ray_pos, ray_dir) = utils::ray_from_mouse_position(window, camera, camera_transform);
+    let point = utils::plane_intersection(ray_pos, ray_dir, Vec3::ZERO, Vec3::Y);
+    let hex = grid.layout.from_world(point);
+    let (entity, species) = match grid.get(hex) {
+        Some(&entity) => (entity, balls.get(entity).ok().flatten()),
+        None => return,
+    };
+
+    egui::show_tooltip_at_pointer(
+        egui_context.ctx_mut(),
+        egui::Id::new("ball inspector"),
+        |ui| {
+            ui.label(format!("{:?}", entity));
+            ui.label(format!("{:?}", hex));
+            ui.label(match species {
+                Some(species) => format!("{:?}", species),
+                None => "obstacle".to_string(),
+            });
+        },
+    );
+}
+
 pub struct DiagnosticsPlugin;
 
 impl Plugin for DiagnosticsPlugin {
@@ -35,6 +115,7 @@ impl Plugin for DiagnosticsPlugin {
             .add_plugin(bevy::diagnostic::DiagnosticsPlugin)
             .add_plugin(bevy::diagnostic::FrameTimeDiagnosticsPlugin)
             .add_system(egui_display_diagnostics)
+            .add_system(egui_inspect_hovered_ball)
             .add_plugin(WorldInspectorPlugin::new());
     }
 }
@@ -1,29 +1,25 @@
+use crate::leaderboard::{spawn_leaderboard, Leaderboard};
 use crate::loading::FontAssets;
+use crate::localization::Localization;
+use crate::navigation::{nav_input, reset_menu_navigation, KeepMenuFocus, MenuFocus, NavInput};
+use crate::theme::{FontScale, Theme};
 use crate::{gameplay, AppState};
 use bevy::prelude::*;
 
-struct ButtonColors {
-    normal: UiColor,
-    hovered: UiColor,
-}
-
 #[derive(Component)]
 pub struct Root;
 
-impl Default for ButtonColors {
-    fn default() -> Self {
-        ButtonColors {
-            normal: Color::rgb(1., 0.1, 0.1).into(),
-            hovered: Color::rgb(1., 0.35, 0.35).into(),
-        }
-    }
-}
-
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
-    button_colors: Res<ButtonColors>,
+    theme: Res<Theme>,
     score: Res<gameplay::Score>,
+    history: Res<gameplay::TurnHistory>,
+    hotseat: Res<gameplay::Hotseat>,
+    mode: Res<gameplay::GameMode>,
+    leaderboard: Res<Leaderboard>,
+    localization: Res<Localization>,
+    font_scale: Res<FontScale>,
 ) {
     commands.spawn_bundle(Camera2dBundle::default());
 
@@ -36,7 +32,7 @@ fn setup_menu(
                 flex_direction: FlexDirection::ColumnReverse,
                 ..Default::default()
             },
-            color: Color::rgb(0.1, 0.1, 0.1).into(),
+            color: theme.game_over.clear_color.into(),
             ..Default::default()
         })
         .insert(Root)
@@ -50,17 +46,18 @@ fn setup_menu(
                         align_items: AlignItems::Center,
                         ..Default::default()
                     },
-                    color: button_colors.normal,
+                    // the only button, focused from the start
+                    color: theme.game_over.button.hovered,
                     ..Default::default()
                 })
                 .with_children(|parent| {
                     parent.spawn_bundle(TextBundle {
                         text: Text {
                             sections: vec![TextSection {
-                                value: "Return".to_string(),
+                                value: localization.get("return").to_string(),
                                 style: TextStyle {
                                     font: font_assets.fira_sans.clone(),
-                                    font_size: 40.0,
+                                    font_size: font_scale.size(40.0),
                                     color: Color::rgb(0.9, 0.9, 0.9),
                                 },
                             }],
@@ -74,10 +71,10 @@ fn setup_menu(
             parent.spawn_bundle(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: format!(" Score: {:?} ", score.0).to_string(),
+                        value: localization.format("score", &[&score.0]),
                         style: TextStyle {
                             font: font_assets.fira_sans.clone(),
-                            font_size: 40.0,
+                            font_size: font_scale.size(40.0),
                             color: Color::rgb(0.9, 0.9, 0.9),
                         },
                     }],
@@ -86,11 +83,84 @@ fn setup_menu(
                 transform: Transform::from_xyz(0.0, 100.0, 0.0),
                 ..Default::default()
             });
+        })
+        .with_children(|parent| {
+            if !hotseat.enabled {
+                return;
+            }
+            let value = match gameplay::Hotseat::winner(&history) {
+                Some(player) => localization.format("player_wins", &[&(player + 1)]),
+                None => localization.get("draw").to_string(),
+            };
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![TextSection {
+                        value,
+                        style: TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: font_scale.size(40.0),
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    }],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+        })
+        .with_children(|parent| {
+            if let Some(best) = history.best() {
+                parent.spawn_bundle(TextBundle {
+                    text: Text {
+                        sections: vec![TextSection {
+                            value: localization.format("best_turn", &[&best.score, &best.turn]),
+                            style: TextStyle {
+                                font: font_assets.fira_sans.clone(),
+                                font_size: font_scale.size(24.0),
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        }],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                });
+            }
+        })
+        .with_children(|parent| {
+            if *mode != gameplay::GameMode::SuddenDeath {
+                return;
+            }
+            parent.spawn_bundle(TextBundle {
+                text: Text {
+                    sections: vec![
+                        TextSection {
+                            value: localization.format("streak", &[&history.longest_streak()]),
+                            style: TextStyle {
+                                font: font_assets.fira_sans.clone(),
+                                font_size: font_scale.size(24.0),
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        },
+                        TextSection {
+                            value: localization.format("best_streak", &[&leaderboard.best_streak]),
+                            style: TextStyle {
+                                font: font_assets.fira_sans.clone(),
+                                font_size: font_scale.size(24.0),
+                                color: Color::rgb(0.9, 0.9, 0.9),
+                            },
+                        },
+                    ],
+                    alignment: Default::default(),
+                },
+                ..Default::default()
+            });
+        })
+        .with_children(|parent| {
+            spawn_leaderboard(parent, &leaderboard, &font_assets, *font_scale);
         });
 }
 
 fn click_play_button(
-    button_colors: Res<ButtonColors>,
+    theme: Res<Theme>,
     mut state: ResMut<State<AppState>>,
     mut interaction_query: Query<
         (&Interaction, &mut UiColor),
@@ -103,12 +173,32 @@ fn click_play_button(
                 state.set(AppState::Menu).unwrap();
             }
             Interaction::Hovered => {
-                *color = button_colors.hovered;
+                *color = theme.game_over.button.hovered;
             }
             Interaction::None => {
-                *color = button_colors.normal;
+                *color = theme.game_over.button.normal;
+            }
+        }
+    }
+}
+
+fn navigate_menu(
+    keys: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    theme: Res<Theme>,
+    mut state: ResMut<State<AppState>>,
+    mut buttons: Query<&mut UiColor, With<Button>>,
+) {
+    match nav_input(&keys, &gamepad_buttons) {
+        Some(NavInput::Activate) => {
+            state.set(AppState::Menu).unwrap();
+        }
+        Some(_) => {
+            for mut color in &mut buttons {
+                *color = theme.game_over.button.hovered;
             }
         }
+        None => {}
     }
 }
 
@@ -125,9 +215,18 @@ pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ButtonColors>()
-            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(setup_menu))
-            .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(click_play_button))
+        app.init_resource::<MenuFocus>()
+            .init_resource::<KeepMenuFocus>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(setup_menu)
+                    .with_system(reset_menu_navigation),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::GameOver)
+                    .with_system(click_play_button)
+                    .with_system(navigate_menu),
+            )
             .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(cleanup_menu));
     }
 }
@@ -1,16 +1,36 @@
 use crate::{
-    ball, grid, hex,
-    loading::{AudioAssets, FontAssets, TextureAssets},
-    projectile, AppState,
+    ball,
+    bindings::KeyBindings,
+    debug::DebugLinesExt,
+    grid, hex,
+    loading::{AudioAssets, FontAssets},
+    localization::Localization,
+    projectile,
+    save::ResumeGame,
+    theme::{FlashColor, FontScale},
+    AppState, ReduceMotion,
 };
-use bevy::{prelude::*, render::camera::Projection};
+use bevy::{ecs::system::SystemParam, prelude::*, render::camera::Projection};
 use bevy_kira_audio::prelude::*;
 use bevy_mod_check_filter::IsTrue;
 use bevy_prototype_debug_lines::DebugLines;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    marker::PhantomData,
+};
 
 #[derive(Component)]
 pub struct MainCamera;
 
+/// Marks a faint placeholder for a ball of the next descent row.
+#[derive(Component)]
+struct RowPreview;
+
+/// Marks the gameplay HUD text.
+#[derive(Component)]
+struct ScoreText;
+
 #[derive(Debug, Clone, Deref, DerefMut)]
 pub struct Score(pub u32);
 
@@ -20,15 +40,297 @@ pub struct TurnCounter(pub u32);
 #[derive(Debug, Clone)]
 pub struct BeginTurn;
 
+/// Phase of the current turn, the projectile is only fired while [TurnPhase::Aiming].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TurnPhase {
+    /// Waiting for the next projectile to be loaded.
+    #[default]
+    Ready,
+    Aiming,
+    Flying,
+    /// The projectile is being snapped and clusters cleared, ends with [BeginTurn].
+    Resolving,
+}
+
+/// Sent when the last ball of a species is removed from the grid.
+#[derive(Debug, Clone)]
+pub struct SpeciesCleared(pub ball::Species);
+
+/// Outcome of a single turn.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TurnRecord {
+    pub turn: u32,
+    /// Hex the projectile was placed at.
+    pub hex: hex::Coord,
+    pub species: ball::Species,
+    /// Number of balls cleared.
+    pub cleared: u32,
+    pub score: u32,
+    /// Cells changed during the turn.
+    pub diff: grid::GridDiff,
+}
+
+/// Turn-by-turn history of the current game.
+#[derive(Debug, Clone, Default, Deref, DerefMut)]
+pub struct TurnHistory(pub Vec<TurnRecord>);
+
+impl TurnHistory {
+    /// Returns the highest scoring turn, the earliest one wins ties.
+    pub fn best(&self) -> Option<&TurnRecord> {
+        self.iter().rev().max_by_key(|record| record.score)
+    }
+
+    /// Returns the longest run of consecutive scoring turns.
+    pub fn longest_streak(&self) -> usize {
+        self.split(|record| record.score == 0)
+            .map(|run| run.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns `true` if the latest turn didn't score.
+    pub fn last_wasted(&self) -> bool {
+        self.last().map_or(false, |record| record.score == 0)
+    }
+}
+
+/// Rules the current game is played with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    /// The grid never moves down and the game can't be lost.
+    Practice,
+    /// Any shot that doesn't score ends the game.
+    SuddenDeath,
+    /// The grid moves down every [DescentTimer] interval instead of after a number of shots.
+    Timed,
+}
+
+impl GameMode {
+    /// Returns true if the grid moves down after a number of shots, see [DescentSchedule].
+    pub fn descends_by_turn(self) -> bool {
+        matches!(self, GameMode::Normal | GameMode::SuddenDeath)
+    }
+}
+
+/// Moves the grid down every `interval` seconds in [GameMode::Timed].
+#[derive(Debug, Clone)]
+pub struct DescentTimer {
+    pub interval: f32,
+    timer: Timer,
+    /// Running while the grid slides down after a descent.
+    slide: Option<Timer>,
+}
+
+impl DescentTimer {
+    /// Returns the seconds left until the grid moves down.
+    pub fn remaining(&self) -> f32 {
+        (self.timer.duration() - self.timer.elapsed()).as_secs_f32()
+    }
+
+    /// Returns true while the grid slides down, the loaded projectile can't be fired until
+    /// it has settled.
+    pub fn is_sliding(&self) -> bool {
+        self.slide.is_some()
+    }
+
+    fn restart(&mut self) {
+        self.timer = Timer::from_seconds(self.interval.max(0.1), false);
+        self.slide = None;
+    }
+}
+
+impl Default for DescentTimer {
+    fn default() -> Self {
+        Self {
+            interval: 10.0,
+            timer: Timer::from_seconds(10.0, false),
+            slide: None,
+        }
+    }
+}
+
+/// Seconds before a descent in [GameMode::Timed] that its row is previewed.
+const TIMED_DESCENT_PREVIEW: f32 = 3.0;
+
+/// Local two-player mode where players alternate turns on the same board.
+#[derive(Debug, Clone, Default)]
+pub struct Hotseat {
+    pub enabled: bool,
+}
+
+impl Hotseat {
+    /// Returns the index of the player taking `turn`.
+    pub fn player(turn: u32) -> usize {
+        (turn.saturating_sub(1) % 2) as usize
+    }
+
+    /// Returns the score of each player.
+    pub fn scores(history: &TurnHistory) -> [u32; 2] {
+        let mut scores = [0; 2];
+        for record in history.iter() {
+            scores[Self::player(record.turn)] += record.score;
+        }
+        scores
+    }
+
+    /// Returns the index of the player with the highest score, `None` on a tie.
+    pub fn winner(history: &TurnHistory) -> Option<usize> {
+        let [first, second] = Self::scores(history);
+        match first.cmp(&second) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+/// Placements of the previous game, drawn as ghost markers on the matching turn. Toggled from
+/// the settings menu.
+///
+/// Boards aren't seeded, so a new game starts from a different board than the recorded one and
+/// the markers only compare pacing, not where the same shot would land. The recorded game is the
+/// previous one of this session, it isn't saved.
+#[derive(Debug, Clone, Default)]
+pub struct GhostRun {
+    pub enabled: bool,
+    pub turns: Vec<TurnRecord>,
+}
+
+/// Number of turns between each time the grid moves down, shrinking as the game goes on.
+#[derive(Debug, Clone)]
+pub struct DescentSchedule {
+    /// `(from_turn, interval)` steps sorted by `from_turn`.
+    pub steps: Vec<(u32, u32)>,
+}
+
+impl DescentSchedule {
+    /// Returns the number of turns between descents from `turn` onwards.
+    pub fn interval(&self, turn: u32) -> u32 {
+        self.steps
+            .iter()
+            .take_while(|(from, _)| *from <= turn)
+            .last()
+            .map_or(5, |(_, interval)| *interval)
+            .max(1)
+    }
+
+    /// Returns the first turn at or after `turn` where the grid moves down.
+    pub fn next_descent(&self, turn: u32) -> u32 {
+        let mut next = 0;
+        while next < turn {
+            next += self.interval(next);
+        }
+        next
+    }
+
+    /// Returns true if the grid moves down at the end of `turn`.
+    pub fn is_descent_turn(&self, turn: u32) -> bool {
+        turn > 0 && self.next_descent(turn) == turn
+    }
+}
+
+impl Default for DescentSchedule {
+    fn default() -> Self {
+        Self {
+            steps: vec![(0, 5), (30, 4), (60, 3)],
+        }
+    }
+}
+
+/// Tweakable gameplay rules.
+#[derive(Debug, Clone)]
+pub struct GameplaySettings {
+    /// Minimum number of matching balls needed to clear a cluster.
+    pub min_cluster_size: usize,
+    /// Score awarded when the last ball of a species is cleared.
+    pub species_clear_bonus: u32,
+    /// Clear matching clusters formed by the grid moving down.
+    pub match_after_move_down: bool,
+    /// Let balls settle into empty cells below them after clears.
+    pub compact_after_clear: bool,
+    /// Drop balls left hanging after a clear, otherwise only matched clusters are removed.
+    pub drop_floating: bool,
+    /// Reload the same species after a shot that scored nothing.
+    pub keep_color_until_score: bool,
+    /// Make sure the loaded or next projectile matches a species on the grid.
+    pub usable_color_assist: bool,
+    /// Lose once the grid spans more rows than this, counted from the top row.
+    pub max_rows: Option<u32>,
+    pub descent: DescentSchedule,
+}
+
+impl GameplaySettings {
+    /// Returns [GameplaySettings::min_cluster_size] clamped to at least 2.
+    pub fn min_cluster_size(&self) -> usize {
+        self.min_cluster_size.max(2)
+    }
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        Self {
+            min_cluster_size: 3,
+            species_clear_bonus: 5,
+            match_after_move_down: false,
+            compact_after_clear: false,
+            drop_floating: true,
+            keep_color_until_score: false,
+            usable_color_assist: false,
+            max_rows: None,
+            descent: Default::default(),
+        }
+    }
+}
+
 pub const PLAYER_SPAWN_Z: f32 = 40.0;
 
+/// World position projectiles are spawned at and aimed from.
+#[derive(Debug, Clone, Copy, Deref, DerefMut)]
+pub struct ShooterPosition(pub Vec3);
+
+impl Default for ShooterPosition {
+    fn default() -> Self {
+        Self(Vec3::new(0.0, 0.0, PLAYER_SPAWN_Z))
+    }
+}
+
 fn setup_gameplay(
     mut begin_turn: EventWriter<BeginTurn>,
     mut turn_counter: ResMut<TurnCounter>,
     mut score: ResMut<Score>,
+    mut history: ResMut<TurnHistory>,
+    mut ghost: ResMut<GhostRun>,
+    mut phase: ResMut<TurnPhase>,
+    mut delay: ResMut<ResolveDelay>,
+    mut fade: ResMut<GameOverFade>,
+    mut lives: ResMut<Lives>,
+    mut descent_timer: ResMut<DescentTimer>,
+    resume: Res<ResumeGame>,
 ) {
-    score.0 = 0;
-    turn_counter.0 = 0;
+    fade.0 = None;
+    descent_timer.restart();
+    lives.left = lives.max.max(1);
+    if !history.is_empty() {
+        ghost.turns = std::mem::take(&mut history.0);
+    }
+    history.clear();
+    match &resume.0 {
+        Some(saved) => {
+            score.0 = saved.score;
+            // the turn is advanced again by `BeginTurn`
+            turn_counter.0 = saved.turn.saturating_sub(1);
+            history.0 = saved.history.clone();
+            lives.left = saved.lives.unwrap_or(lives.left).clamp(1, lives.left);
+        }
+        None => {
+            score.0 = 0;
+            turn_counter.0 = 0;
+        }
+    }
+    *phase = TurnPhase::Ready;
+    delay.0 = None;
     begin_turn.send(BeginTurn);
 }
 
@@ -40,23 +342,169 @@ fn on_begin_turn(mut turn_counter: ResMut<TurnCounter>, begin_turn: EventReader<
     turn_counter.0 += 1;
 }
 
+#[derive(SystemParam)]
+struct ScoreFeedback<'w, 's> {
+    audio: Res<'w, bevy_kira_audio::Audio>,
+    audio_assets: Res<'w, AudioAssets>,
+    flash: ResMut<'w, FlashColor>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// Thud played once the grid has settled after moving down.
+#[derive(Debug, Clone)]
+pub struct SettleSound {
+    pub enabled: bool,
+    /// Volume when a board as full as a new game's moved, emptier boards play quieter.
+    pub volume: f64,
+}
+
+impl SettleSound {
+    /// Returns the volume of a descent moving `balls`, relative to the `full` board of a new
+    /// game.
+    fn volume(&self, balls: usize, full: usize) -> f64 {
+        self.volume * (balls as f64 / full.max(1) as f64).clamp(0.25, 1.0)
+    }
+}
+
+impl Default for SettleSound {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            volume: 0.6,
+        }
+    }
+}
+
+fn play_settle_sound(
+    mut descent_settled: EventReader<grid::DescentSettled>,
+    sound: Res<SettleSound>,
+    settings: Res<grid::GridSettings>,
+    audio: Res<bevy_kira_audio::Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    // play at most once per frame, however many descents settled
+    let balls = match descent_settled.iter().map(|settled| settled.balls).max() {
+        Some(balls) => balls,
+        None => return,
+    };
+    if !sound.enabled {
+        return;
+    }
+
+    let full = (settings.width * settings.height).max(0) as usize;
+    audio
+        .play(audio_assets.settle.clone())
+        .with_playback_rate(0.5)
+        .with_volume(sound.volume(balls, full));
+}
+
+/// Delays [BeginTurn] while the grid slides down, see [grid::DESCENT_DURATION].
+#[derive(Default)]
+pub struct ResolveDelay(pub Option<Timer>);
+
+#[derive(SystemParam)]
+struct TurnState<'w, 's> {
+    counter: Res<'w, TurnCounter>,
+    phase: ResMut<'w, TurnPhase>,
+    delay: ResMut<'w, ResolveDelay>,
+    begin: EventWriter<'w, 's, BeginTurn>,
+}
+
+/// Balls removed by a single shot. Removing them through it keeps the grid, the score and
+/// the feedback in step.
+#[derive(Default)]
+struct Clearing {
+    score: u32,
+    /// World positions and species of removed balls, used for score feedback.
+    cleared: Vec<(Vec2, Option<ball::Species>)>,
+    /// Entities removed from the grid, despawned through the [ClearQueue].
+    removed: Vec<Entity>,
+}
+
+impl Clearing {
+    /// Hits the ball at `hex` as part of a match, armored balls lose health instead.
+    fn hit(
+        &mut self,
+        commands: &mut Commands,
+        grid: &mut grid::Grid,
+        hex: hex::Coord,
+        species_of: impl Fn(&Entity) -> Option<ball::Species>,
+    ) {
+        let species = grid.get(hex).and_then(species_of);
+        if let Some(entity) = grid.clear_ball(commands, hex) {
+            self.removed.push(entity);
+            self.cleared.push((grid.layout.to_world(hex), species));
+        }
+        // armored balls score every hit, not just the one removing them
+        self.score += 1;
+    }
+
+    /// Removes the floating ball at `hex`, regardless of its health.
+    fn drop_ball(
+        &mut self,
+        grid: &mut grid::Grid,
+        hex: hex::Coord,
+        species_of: impl Fn(&Entity) -> Option<ball::Species>,
+    ) {
+        if let Some(entity) = grid.set(hex, None) {
+            self.removed.push(entity);
+            self.cleared
+                .push((grid.layout.to_world(hex), species_of(&entity)));
+            self.score += 1;
+        }
+    }
+
+    /// Drops every floating ball, unless [GameplaySettings::drop_floating] is disabled.
+    fn drop_floating(
+        &mut self,
+        settings: &GameplaySettings,
+        grid: &mut grid::Grid,
+        species_of: impl Fn(&Entity) -> Option<ball::Species>,
+        scratch: &mut grid::ClusterScratch,
+    ) {
+        if !settings.drop_floating {
+            return;
+        }
+
+        let floating_clusters = grid::find_floating_clusters(grid, scratch);
+        for &hex in floating_clusters.iter().flatten() {
+            self.drop_ball(grid, hex, &species_of);
+        }
+    }
+}
+
+/// Looks up the species of a grid ball, including balls spawned this frame.
+fn species_of(
+    entity: &Entity,
+    pending: &HashMap<Entity, ball::Species>,
+    balls: &Query<&ball::Species, With<ball::Ball>>,
+) -> Option<ball::Species> {
+    pending
+        .get(entity)
+        .copied()
+        .or_else(|| balls.get(*entity).ok().copied())
+}
+
 fn on_snap_projectile(
     snap_projectile: EventReader<projectile::SnapProjectile>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: ball::BallAssets,
     mut grid: ResMut<grid::Grid>,
-    mut begin_turn: EventWriter<BeginTurn>,
     mut score: ResMut<Score>,
-    turn_counter: ResMut<TurnCounter>,
+    mut turn: TurnState,
     projectile: Query<
-        (Entity, &Transform, &ball::Species),
+        (Entity, &Transform, &ball::Species, &projectile::Charge),
         (With<projectile::Projectile>, IsTrue<projectile::Flying>),
     >,
     balls: Query<&ball::Species, With<ball::Ball>>,
-    texture_assets: Res<TextureAssets>,
-    audio: Res<bevy_kira_audio::Audio>,
-    audio_assets: Res<AudioAssets>,
+    mut feedback: ScoreFeedback,
+    settings: Res<GameplaySettings>,
+    mut history: ResMut<TurnHistory>,
+    mode: Res<GameMode>,
+    mut species_cleared: EventWriter<SpeciesCleared>,
+    mut clear_queue: ResMut<ClearQueue>,
+    mut scratch: ResMut<grid::ClusterScratch>,
 ) {
     if snap_projectile.is_empty() {
         return;
@@ -65,9 +513,16 @@ fn on_snap_projectile(
     // We really only care about the first ball hit event
     snap_projectile.clear();
 
-    if let Ok((entity, tr, species)) = projectile.get_single() {
+    if *turn.phase != TurnPhase::Flying {
+        return;
+    }
+
+    if let Ok((entity, tr, species, charge)) = projectile.get_single() {
+        *turn.phase = TurnPhase::Resolving;
         commands.entity(entity).despawn();
 
+        let before = grid.snapshot(|e| balls.get(*e).ok().copied());
+
         let y = tr.translation.y;
         let mut translation = tr.translation;
         let mut hex = grid.layout.from_world(translation);
@@ -106,86 +561,418 @@ fn on_snap_projectile(
         let ball = commands
             .spawn_bundle(ball::BallBundle::new(
                 final_pos,
-                grid.layout.size.x,
+                grid.layout.radius(),
                 *species,
-                &mut meshes,
-                &mut materials,
-                &texture_assets,
+                &mut assets,
             ))
             .insert(hex)
             .id();
 
         grid.set(hex, Some(ball));
 
-        let (cluster, _) = grid::find_cluster(&grid, hex, |&e| {
-            e == ball
-                || match balls.get(e) {
-                    Ok(other) => *other == *species,
-                    Err(_) => false,
+        // freshly spawned balls aren't queryable until commands are applied
+        let mut pending = HashMap::from([(ball, *species)]);
+        // cells filled by this shot, each one can start a match
+        let mut placed = vec![hex];
+
+        // a charged projectile also fills the empty cells around it
+        let reach = (charge.scale - 1.0).round().max(0.0) as usize;
+        let (hex_width, hex_height) = grid.layout.hex_size();
+        let mut visited = HashSet::from([hex]);
+        let mut frontier = vec![hex];
+        for _ in 0..reach {
+            frontier = frontier
+                .iter()
+                .flat_map(|hex| hex.neighbors())
+                .filter(|&hex| visited.insert(hex))
+                .collect();
+
+            for &hex in frontier.iter() {
+                let pos = grid.layout.to_world(hex);
+                let inside = pos.x >= grid.bounds.mins.x + hex_width
+                    && pos.x <= grid.bounds.maxs.x - hex_width
+                    && pos.y >= grid.bounds.mins.y + hex_height;
+                if !inside || grid.get(hex).is_some() {
+                    continue;
                 }
-        });
 
-        let mut score_add = 0;
+                // copies of the projectile's species would make a match on their own
+                let fill = projectile::random_live_species(&grid);
+                let ball = commands
+                    .spawn_bundle(ball::BallBundle::new(
+                        grid.layout.to_world_y(hex, y),
+                        grid.layout.radius(),
+                        fill,
+                        &mut assets,
+                    ))
+                    .insert(hex)
+                    .id();
+
+                grid.set(hex, Some(ball));
+                pending.insert(ball, fill);
+                placed.push(hex);
+            }
+        }
+
+        let mut clearing = Clearing::default();
+        let lookup = |e: &Entity| species_of(e, &pending, &balls);
 
         // remove matching clusters
-        const MIN_CLUSTER_SIZE: usize = 3;
-        if cluster.len() >= MIN_CLUSTER_SIZE {
-            cluster.iter().for_each(|&hex| {
-                commands.entity(*grid.get(hex).unwrap()).despawn();
-                grid.set(hex, None);
-                score_add += 1;
-            });
+        let clusters = grid::find_matching_clusters_from(
+            &grid,
+            placed.iter().copied(),
+            settings.min_cluster_size(),
+            lookup,
+            &mut scratch,
+        );
+        for &hex in clusters.iter().flatten() {
+            clearing.hit(&mut commands, &mut grid, hex, lookup);
         }
 
         // remove floating clusters
-        let floating_clusters = grid::find_floating_clusters(&grid);
-        floating_clusters
-            .iter()
-            .flat_map(|e| e.iter())
-            .for_each(|&hex| {
-                commands.entity(*grid.get(hex).unwrap()).despawn();
-                grid.set(hex, None);
-                score_add += 1;
-            });
-
-        const MOVE_DOWN_TURN: u32 = 5;
-        if turn_counter.0 % MOVE_DOWN_TURN == 0 {
-            grid::move_down_and_spawn(
+        clearing.drop_floating(&settings, &mut grid, lookup, &mut scratch);
+
+        if settings.compact_after_clear {
+            for (entity, hex) in grid::compact_down(grid.as_mut()) {
+                commands.entity(entity).insert(hex);
+            }
+        }
+
+        let moved_down =
+            mode.descends_by_turn() && settings.descent.is_descent_turn(turn.counter.0);
+        if moved_down {
+            let before = grid.storage.clone();
+            pending.extend(grid::move_down_and_spawn(
                 &mut commands,
-                meshes,
-                materials,
+                &mut assets,
                 grid.as_mut(),
-                &texture_assets,
-            );
+            ));
+            let lookup = |e: &Entity| species_of(e, &pending, &balls);
+
+            // remove matching clusters formed by moving down, searching from the new row and
+            // balls that moved next to another so untouched clusters of the board stay
+            if settings.match_after_move_down {
+                let clusters = grid::find_matching_clusters_from(
+                    &grid,
+                    grid::new_contacts(&before, &grid),
+                    settings.min_cluster_size(),
+                    lookup,
+                    &mut scratch,
+                );
+
+                for &hex in clusters.iter().flatten() {
+                    clearing.hit(&mut commands, &mut grid, hex, lookup);
+                }
+            }
         }
 
         // remove floating clusters
-        let floating_clusters = grid::find_floating_clusters(&grid);
-        floating_clusters
-            .iter()
-            .flat_map(|e| e.iter())
-            .for_each(|&hex| {
-                commands.entity(*grid.get(hex).unwrap()).despawn();
-                grid.set(hex, None);
-                score_add += 1;
-            });
+        let lookup = |e: &Entity| species_of(e, &pending, &balls);
+        clearing.drop_floating(&settings, &mut grid, lookup, &mut scratch);
+
+        let after = grid.snapshot(|e| {
+            pending
+                .get(e)
+                .copied()
+                .or_else(|| balls.get(*e).ok().copied())
+        });
+
+        let live_species: HashSet<ball::Species> = after.values().copied().collect();
+        let mut score_bonus = 0;
+        for &cleared in grid.live_species.difference(&live_species) {
+            info!("{:?} cleared!", cleared);
+            species_cleared.send(SpeciesCleared(cleared));
+            score_bonus += settings.species_clear_bonus;
+        }
+        grid.live_species = live_species;
+
+        let Clearing {
+            score: score_add,
+            cleared,
+            removed,
+        } = clearing;
 
         if score_add > 0 {
-            audio.play(audio_assets.score.clone());
+            let center = (grid.bounds.mins.x + grid.bounds.maxs.x) / 2.0;
+            let (width, _) = grid.dim();
+            let x = cleared.iter().map(|(pos, _)| pos.x).sum::<f32>() / cleared.len().max(1) as f32;
+            let panning = match width > 0.0 && !cleared.is_empty() {
+                true => (0.5 + (x - center) / width).clamp(0.0, 1.0),
+                false => 0.5,
+            };
+            feedback
+                .audio
+                .play(feedback.audio_assets.score.clone())
+                .with_panning(panning as f64);
         }
 
-        score.0 += score_add;
+        if cleared.len() >= feedback.flash.min_cleared {
+            let mut counts: HashMap<ball::Species, usize> = HashMap::new();
+            for species in cleared.iter().filter_map(|(_, species)| *species) {
+                *counts.entry(species).or_default() += 1;
+            }
+            if let Some((&dominant, _)) = counts.iter().max_by_key(|(_, &count)| count) {
+                feedback.flash.trigger(ball::species_to_color(dominant));
+            }
+        }
+
+        score.0 += score_add + score_bonus;
+
+        history.push(TurnRecord {
+            turn: turn.counter.0,
+            hex,
+            species: *species,
+            cleared: score_add,
+            score: score_add + score_bonus,
+            diff: grid::GridDiff::between(&before, &after),
+        });
+
+        clear_queue.push(&mut commands, removed);
 
+        // a wasted shot stays resolving until check_game_over ends the game
+        if *mode == GameMode::SuddenDeath && history.last_wasted() {
+            return;
+        }
+
+        match (moved_down, clear_queue.is_empty()) {
+            (false, true) => {
+                *turn.phase = TurnPhase::Ready;
+                turn.begin.send(BeginTurn);
+            }
+            (true, _) => {
+                turn.delay.0 = Some(Timer::from_seconds(grid::DESCENT_DURATION, false));
+            }
+            // finish_resolving waits for the queue to drain
+            (false, false) => {
+                turn.delay.0 = Some(Timer::from_seconds(0.0, false));
+            }
+        }
+    }
+}
+
+/// Balls removed from the grid that haven't been despawned yet. Big clears are despawned over
+/// several frames, at most `budget` balls per frame, and are hidden until then.
+#[derive(Debug, Clone)]
+pub struct ClearQueue {
+    pub budget: usize,
+    entities: VecDeque<Entity>,
+}
+
+impl Default for ClearQueue {
+    fn default() -> Self {
+        Self {
+            budget: 64,
+            entities: VecDeque::new(),
+        }
+    }
+}
+
+impl ClearQueue {
+    /// Despawns `entities` right away if they fit the budget, otherwise hides and queues them.
+    fn push(&mut self, commands: &mut Commands, entities: Vec<Entity>) {
+        if self.entities.is_empty() && entities.len() <= self.budget {
+            for entity in entities {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+
+        for entity in entities {
+            commands
+                .entity(entity)
+                .insert(Visibility { is_visible: false });
+            self.entities.push_back(entity);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+fn drain_clear_queue(
+    mut commands: Commands,
+    mut queue: ResMut<ClearQueue>,
+    paused: Res<projectile::Paused>,
+) {
+    if paused.0 {
+        return;
+    }
+
+    let count = queue.budget.max(1).min(queue.entities.len());
+    for entity in queue.entities.drain(..count) {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.despawn();
+        }
+    }
+}
+
+/// Begins the next turn once the grid has finished sliding down and cleared balls are gone.
+pub fn finish_resolving(
+    queue: Res<ClearQueue>,
+    mut delay: ResMut<ResolveDelay>,
+    mut phase: ResMut<TurnPhase>,
+    mut begin_turn: EventWriter<BeginTurn>,
+    reduce_motion: Res<ReduceMotion>,
+    fade: Res<GameOverFade>,
+    paused: Res<projectile::Paused>,
+    time: Res<Time>,
+) {
+    // the game is over, the remaining shots are never taken
+    if paused.0 || fade.is_running() {
+        return;
+    }
+
+    let finished = match &mut delay.0 {
+        Some(timer) => reduce_motion.0 || timer.tick(time.delta()).finished(),
+        None => return,
+    };
+
+    if finished && queue.is_empty() {
+        delay.0 = None;
+        *phase = TurnPhase::Ready;
         begin_turn.send(BeginTurn);
     }
 }
 
+/// Seconds the remaining balls take to shrink away before the game over menu appears.
+const GAME_OVER_FADE: f32 = 0.5;
+
+/// Lives of the player, reaching the game over row costs one and clears the bottom rows
+/// instead of ending the game, until the last one is lost.
+#[derive(Debug, Clone)]
+pub struct Lives {
+    /// Lives at the start of a game, 1 ends the game on the first loss.
+    pub max: u32,
+    /// Number of rows cleared from the bottom of the grid when a life is lost.
+    pub cleared_rows: i32,
+    left: u32,
+}
+
+impl Lives {
+    /// Returns the number of lives left, including the current one.
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self {
+            max: 1,
+            cleared_rows: 3,
+            left: 1,
+        }
+    }
+}
+
+/// Removes every ball in the bottom `rows` rows of the grid.
+fn clear_bottom_rows(commands: &mut Commands, grid: &mut grid::Grid, rows: i32) {
+    let bottom = match grid.storage.keys().map(|hex| hex.r).max() {
+        Some(bottom) => bottom,
+        None => return,
+    };
+
+    let cleared = grid
+        .storage
+        .keys()
+        .filter(|hex| hex.r > bottom - rows.max(1))
+        .copied()
+        .collect::<Vec<_>>();
+    for hex in cleared {
+        if let Some(entity) = grid.set(hex, None) {
+            grid.obstacles.remove(&entity);
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Moves the grid down once the [DescentTimer] runs out in [GameMode::Timed].
+fn tick_descent_timer(
+    mut commands: Commands,
+    mut assets: ball::BallAssets,
+    mut grid: ResMut<grid::Grid>,
+    mut descent_timer: ResMut<DescentTimer>,
+    mode: Res<GameMode>,
+    phase: Res<TurnPhase>,
+    paused: Res<projectile::Paused>,
+    fade: Res<GameOverFade>,
+    reduce_motion: Res<ReduceMotion>,
+    time: Res<Time>,
+) {
+    if *mode != GameMode::Timed || paused.0 {
+        return;
+    }
+
+    // follows grid::animate_slides, which also runs while the grid fades out
+    let settled = match &mut descent_timer.slide {
+        Some(slide) => reduce_motion.0 || slide.tick(time.delta()).finished(),
+        None => false,
+    };
+    if settled {
+        descent_timer.slide = None;
+    }
+
+    if fade.is_running() {
+        return;
+    }
+
+    descent_timer.timer.tick(time.delta());
+    // a shot in flight snaps to the grid, wait until it's resolved
+    if !descent_timer.timer.finished() || *phase != TurnPhase::Aiming {
+        return;
+    }
+
+    let spawned = grid::move_down_and_spawn(&mut commands, &mut assets, grid.as_mut());
+    grid.live_species
+        .extend(spawned.into_iter().map(|(_, species)| species));
+    descent_timer.restart();
+    if !reduce_motion.0 {
+        descent_timer.slide = Some(Timer::from_seconds(grid::DESCENT_DURATION, false));
+    }
+}
+
+/// Returns true if the grid spans more than `max_rows` rows, counted from the top row.
+fn exceeds_max_rows(grid: &grid::Grid, max_rows: Option<u32>) -> bool {
+    match max_rows {
+        Some(max_rows) => grid
+            .storage
+            .keys()
+            .map(|hex| hex.r + 1)
+            .max()
+            .map_or(false, |rows| rows > max_rows as i32),
+        None => false,
+    }
+}
+
+/// Running while the grid fades out at the end of a game.
+#[derive(Default)]
+pub struct GameOverFade(Option<Timer>);
+
+impl GameOverFade {
+    /// Returns true while the game is over and the grid is fading out.
+    pub fn is_running(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
 fn check_game_over(
-    grid: Res<grid::Grid>,
+    mut commands: Commands,
+    mut grid: ResMut<grid::Grid>,
+    mut lives: ResMut<Lives>,
+    mode: Res<GameMode>,
+    settings: Res<GameplaySettings>,
+    history: Res<TurnHistory>,
+    reduce_motion: Res<ReduceMotion>,
+    shooter: Res<ShooterPosition>,
+    mut fade: ResMut<GameOverFade>,
+    mut phase: ResMut<TurnPhase>,
     mut app_state: ResMut<State<AppState>>,
     mut lines: ResMut<DebugLines>,
+    balls: Query<&ball::Species, With<ball::Ball>>,
+    mut species_cleared: EventWriter<SpeciesCleared>,
 ) {
-    let projectile_hex = grid.layout.from_world(Vec3::new(0.0, 0.0, PLAYER_SPAWN_Z));
+    let projectile_hex = grid.layout.from_world(shooter.0);
     let game_over_row = projectile_hex
         .neighbor(hex::Direction::B)
         .neighbor(hex::Direction::B);
@@ -198,12 +985,170 @@ fn check_game_over(
         Color::RED,
     );
 
-    for (&hex, _) in grid.storage.iter() {
-        let world_pos = grid.layout.to_world_y(hex, 0.0);
-        if world_pos.z >= row_pos.z - 0.1 {
-            app_state.set(AppState::GameOver).unwrap();
-            break;
+    if *mode == GameMode::Practice || fade.0.is_some() {
+        return;
+    }
+
+    let past_row = grid
+        .closest_to_row(row_pos.z)
+        .map_or(false, |(_, distance)| distance <= 0.1);
+
+    let too_tall = exceeds_max_rows(&grid, settings.max_rows);
+
+    let wasted = *mode == GameMode::SuddenDeath && history.last_wasted();
+
+    if !(past_row || too_tall || wasted) {
+        return;
+    }
+
+    // a wasted shot in sudden death always ends the game
+    if !wasted && lives.left > 1 {
+        lives.left -= 1;
+        clear_bottom_rows(&mut commands, &mut grid, lives.cleared_rows);
+
+        // the cleared rows may have held the last balls of a species
+        let live_species = grid
+            .storage
+            .values()
+            .filter_map(|entity| balls.get(*entity).ok().copied())
+            .collect::<HashSet<_>>();
+        for &cleared in grid.live_species.difference(&live_species) {
+            species_cleared.send(SpeciesCleared(cleared));
         }
+        grid.live_species = live_species;
+        return;
+    }
+
+    match reduce_motion.0 {
+        true => app_state.set(AppState::GameOver).unwrap(),
+        false => {
+            // no more shots while the grid fades out
+            *phase = TurnPhase::Resolving;
+            fade.0 = Some(Timer::from_seconds(GAME_OVER_FADE, false));
+        }
+    }
+}
+
+/// Shrinks the remaining balls, then shows the game over menu.
+fn fade_out_grid(
+    time: Res<Time>,
+    mut fade: ResMut<GameOverFade>,
+    mut balls: Query<&mut Transform, With<ball::Ball>>,
+    mut app_state: ResMut<State<AppState>>,
+    paused: Res<projectile::Paused>,
+) {
+    if paused.0 {
+        return;
+    }
+
+    let timer = match &mut fade.0 {
+        Some(timer) => timer,
+        None => return,
+    };
+
+    timer.tick(time.delta());
+    let scale = 1.0 - timer.percent();
+    for mut transform in balls.iter_mut() {
+        transform.scale = Vec3::splat(scale);
+    }
+
+    if timer.finished() {
+        fade.0 = None;
+        app_state.set(AppState::GameOver).unwrap();
+    }
+}
+
+/// Returns true if the next descent is close enough to preview its row, on the turn it
+/// happens or in the last [TIMED_DESCENT_PREVIEW] seconds before it in [GameMode::Timed].
+fn descent_is_near(
+    mode: GameMode,
+    settings: &GameplaySettings,
+    turn: u32,
+    descent_timer: &DescentTimer,
+) -> bool {
+    match mode {
+        GameMode::Timed => descent_timer.remaining() <= TIMED_DESCENT_PREVIEW,
+        _ => mode.descends_by_turn() && settings.descent.is_descent_turn(turn),
+    }
+}
+
+/// Shows the row spawned by the next descent shortly before it happens.
+fn preview_descent_row(
+    mut commands: Commands,
+    mut assets: ball::BallAssets,
+    mut grid: ResMut<grid::Grid>,
+    turn_counter: Res<TurnCounter>,
+    settings: Res<GameplaySettings>,
+    mode: Res<GameMode>,
+    descent_timer: Res<DescentTimer>,
+    previews: Query<Entity, With<RowPreview>>,
+) {
+    let descending = descent_is_near(*mode, &settings, turn_counter.0, &descent_timer);
+
+    match (descending, previews.is_empty()) {
+        (true, true) => {
+            let row_step = grid.row_step();
+            let radius = grid.layout.radius();
+            for (hex, species) in grid.next_row() {
+                // translucent colors are cached like any other ball color
+                let color = *ball::species_to_color(species).set_a(0.3);
+                commands
+                    .spawn_bundle(PbrBundle {
+                        mesh: assets.mesh(species, radius),
+                        material: assets.material(color),
+                        transform: Transform::from_translation(
+                            grid.layout.to_world_y(hex, 0.0) - Vec3::Z * row_step.z,
+                        ),
+                        ..Default::default()
+                    })
+                    .insert(RowPreview);
+            }
+        }
+        (false, false) => {
+            for entity in previews.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn display_ghost_run(
+    ghost: Res<GhostRun>,
+    grid: Res<grid::Grid>,
+    turn_counter: Res<TurnCounter>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !ghost.enabled {
+        return;
+    }
+
+    if let Some(record) = ghost
+        .turns
+        .iter()
+        .find(|record| record.turn == turn_counter.0)
+    {
+        let (radius, _) = grid.layout.hex_size();
+        let mut color = ball::species_to_color(record.species);
+        color.set_a(0.5);
+        lines.circle(
+            grid.layout.to_world_y(record.hex, 0.0),
+            Quat::IDENTITY,
+            radius * ball::BALL_RADIUS_COEFF,
+            0.0,
+            color,
+        );
+    }
+}
+
+fn reset_practice_board(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mode: Res<GameMode>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if *mode == GameMode::Practice && keys.just_pressed(bindings.restart) {
+        let _ = app_state.restart();
     }
 }
 
@@ -221,37 +1166,119 @@ fn setup_camera(mut commands: Commands) {
         .insert(MainCamera);
 }
 
-fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>, score: Res<Score>) {
-    commands.spawn_bundle(TextBundle {
-        text: Text {
-            sections: vec![TextSection {
-                value: format!(" Score: {:?} ", score.0).to_string(),
-                style: TextStyle {
-                    font: font_assets.fira_sans.clone(),
-                    font_size: 40.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
-            }],
-            alignment: Default::default(),
-        },
-        transform: Transform::from_xyz(0.0, 100.0, 0.0),
-        ..Default::default()
-    });
-}
-
-fn update_ui(score: Res<Score>, mut score_text: Query<&mut Text>) {
+const DESCENT_TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const DESCENT_WARNING_COLOR: Color = Color::ORANGE_RED;
+
+fn setup_ui(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    score: Res<Score>,
+    localization: Res<Localization>,
+    font_scale: Res<FontScale>,
+) {
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: localization.format("score", &[&score.0]),
+                        style: TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: font_scale.size(40.0),
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    },
+                    TextSection {
+                        value: String::new(),
+                        style: TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: font_scale.size(40.0),
+                            color: DESCENT_TEXT_COLOR,
+                        },
+                    },
+                    TextSection {
+                        value: String::new(),
+                        style: TextStyle {
+                            font: font_assets.fira_sans.clone(),
+                            font_size: font_scale.size(40.0),
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                    },
+                ],
+                alignment: Default::default(),
+            },
+            transform: Transform::from_xyz(0.0, 100.0, 0.0),
+            ..Default::default()
+        })
+        .insert(ScoreText);
+}
+
+fn update_ui(
+    score: Res<Score>,
+    hotseat: Res<Hotseat>,
+    history: Res<TurnHistory>,
+    turn_counter: Res<TurnCounter>,
+    settings: Res<GameplaySettings>,
+    mode: Res<GameMode>,
+    lives: Res<Lives>,
+    descent_timer: Res<DescentTimer>,
+    localization: Res<Localization>,
+    mut score_text: Query<&mut Text, With<ScoreText>>,
+) {
+    let value = match hotseat.enabled {
+        true => {
+            let scores = Hotseat::scores(&history);
+            let current = Hotseat::player(turn_counter.0);
+            let marker = |player: usize| if player == current { ">" } else { " " };
+            localization.format(
+                "hotseat_score",
+                &[&marker(0), &scores[0], &marker(1), &scores[1]],
+            )
+        }
+        false => localization.format("score", &[&score.0]),
+    };
+
+    // shots left including the current one before the grid moves down, seconds when timed
+    let shots_left = match *mode {
+        GameMode::Practice => None,
+        GameMode::Normal | GameMode::SuddenDeath => {
+            let turn = turn_counter.0.max(1);
+            Some(settings.descent.next_descent(turn) + 1 - turn)
+        }
+        GameMode::Timed => Some(descent_timer.remaining().ceil() as u32),
+    };
+
     for mut text in &mut score_text {
-        text.sections[0].value = format!(" Score: {:?} ", score.0);
+        text.sections[0].value = value.clone();
+        text.sections[1].value = match shots_left {
+            Some(shots) => localization.format("drop_in", &[&shots]),
+            None => String::new(),
+        };
+        text.sections[1].style.color = match shots_left {
+            Some(1) => DESCENT_WARNING_COLOR,
+            _ => DESCENT_TEXT_COLOR,
+        };
+        text.sections[2].value = match lives.max > 1 && *mode != GameMode::Practice {
+            true => localization.format("lives", &[&lives.left()]),
+            false => String::new(),
+        };
     }
 }
 
 fn cleanup_gameplay(
     mut commands: Commands,
     camera: Query<Entity, With<MainCamera>>,
-    score_text: Query<Entity, With<Text>>,
+    score_text: Query<Entity, With<ScoreText>>,
+    previews: Query<Entity, With<RowPreview>>,
+    mut clear_queue: ResMut<ClearQueue>,
 ) {
+    // queued balls still have their coordinate and are despawned with the grid
+    clear_queue.entities.clear();
     commands.entity(camera.single()).despawn_recursive();
     commands.entity(score_text.single()).despawn_recursive();
+    for entity in previews.iter() {
+        commands.entity(entity).despawn();
+    }
 }
 
 pub struct GameplayPlugin;
@@ -259,8 +1286,22 @@ pub struct GameplayPlugin;
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<BeginTurn>();
+        app.add_event::<SpeciesCleared>();
         app.insert_resource(TurnCounter(0));
         app.insert_resource(Score(0));
+        app.init_resource::<GameplaySettings>();
+        app.init_resource::<TurnHistory>();
+        app.init_resource::<GhostRun>();
+        app.init_resource::<Hotseat>();
+        app.init_resource::<GameMode>();
+        app.init_resource::<TurnPhase>();
+        app.init_resource::<ResolveDelay>();
+        app.init_resource::<ClearQueue>();
+        app.init_resource::<GameOverFade>();
+        app.init_resource::<Lives>();
+        app.init_resource::<DescentTimer>();
+        app.init_resource::<ShooterPosition>();
+        app.init_resource::<SettleSound>();
         app.add_system_set(
             SystemSet::on_enter(AppState::Gameplay)
                 .with_system(setup_ui)
@@ -271,9 +1312,467 @@ impl Plugin for GameplayPlugin {
             SystemSet::on_update(AppState::Gameplay)
                 .with_system(update_ui)
                 .with_system(on_begin_turn)
+                .with_system(drain_clear_queue)
+                .with_system(finish_resolving)
                 .with_system(check_game_over)
+                .with_system(fade_out_grid)
+                .with_system(play_settle_sound)
+                .with_system(tick_descent_timer)
+                .with_system(display_ghost_run)
+                .with_system(preview_descent_row)
+                .with_system(reset_practice_board)
                 .with_system(on_snap_projectile),
         );
         app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(cleanup_gameplay));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::tests::grid_with;
+
+    #[test]
+    fn min_cluster_size_is_at_least_two() {
+        let settings = GameplaySettings {
+            min_cluster_size: 0,
+            ..Default::default()
+        };
+        assert_eq!(settings.min_cluster_size(), 2);
+    }
+
+    #[test]
+    fn descent_interval_shrinks_over_time() {
+        let schedule = DescentSchedule::default();
+        assert_eq!(schedule.interval(1), 5);
+        assert_eq!(schedule.interval(29), 5);
+        assert_eq!(schedule.interval(30), 4);
+        assert_eq!(schedule.interval(59), 4);
+        assert_eq!(schedule.interval(60), 3);
+        assert_eq!(schedule.interval(500), 3);
+    }
+
+    #[test]
+    fn next_descent_follows_the_schedule() {
+        let schedule = DescentSchedule {
+            steps: vec![(0, 5), (10, 2)],
+        };
+        assert_eq!(schedule.next_descent(1), 5);
+        assert_eq!(schedule.next_descent(5), 5);
+        assert_eq!(schedule.next_descent(6), 10);
+        assert_eq!(schedule.next_descent(11), 12);
+
+        let descents = (1..=16)
+            .filter(|&turn| schedule.is_descent_turn(turn))
+            .collect::<Vec<_>>();
+        assert_eq!(descents, vec![5, 10, 12, 14, 16]);
+    }
+
+    fn resolving_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(ClearQueue::default());
+        world.insert_resource(ResolveDelay::default());
+        world.insert_resource(TurnPhase::Resolving);
+        world.insert_resource(Events::<BeginTurn>::default());
+        world.insert_resource(ReduceMotion(false));
+        world.insert_resource(GameOverFade::default());
+        world.insert_resource(projectile::Paused::default());
+        world.insert_resource(Time::default());
+        world
+    }
+
+    fn turns_begun(world: &World) -> usize {
+        let events = world.resource::<Events<BeginTurn>>();
+        events.get_reader().iter(events).count()
+    }
+
+    #[test]
+    fn resolving_waits_for_the_descent_and_clear_queue() {
+        let mut world = resolving_world();
+        let mut stage = SystemStage::single_threaded().with_system(finish_resolving);
+
+        // nothing to wait for until on_snap_projectile sets a delay
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<TurnPhase>(), TurnPhase::Resolving);
+
+        world.resource_mut::<ResolveDelay>().0 =
+            Some(Timer::from_seconds(grid::DESCENT_DURATION, false));
+        world
+            .resource_mut::<ClearQueue>()
+            .entities
+            .push_back(Entity::from_raw(0));
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<TurnPhase>(), TurnPhase::Resolving);
+
+        // skips the slide, the queue still holds the turn back
+        world.resource_mut::<ReduceMotion>().0 = true;
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<TurnPhase>(), TurnPhase::Resolving);
+        assert_eq!(turns_begun(&world), 0);
+
+        world.resource_mut::<ClearQueue>().entities.clear();
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<TurnPhase>(), TurnPhase::Ready);
+        assert_eq!(turns_begun(&world), 1);
+        assert!(world.resource::<ResolveDelay>().0.is_none());
+    }
+
+    #[test]
+    fn max_rows_is_exceeded_by_the_next_row() {
+        let mut rows = vec![];
+        for r in 0..4 {
+            rows.push((0, r));
+            let grid = grid_with(&rows);
+            assert_eq!(exceeds_max_rows(&grid, Some(3)), r >= 3);
+            assert!(!exceeds_max_rows(&grid, None));
+        }
+    }
+
+    fn record(turn: u32, score: u32) -> TurnRecord {
+        TurnRecord {
+            turn,
+            hex: hex::Coord::new(0, 0),
+            species: ball::Species::Red,
+            cleared: score,
+            score,
+            diff: Default::default(),
+        }
+    }
+
+    #[test]
+    fn longest_streak_counts_consecutive_scoring_turns() {
+        let history = TurnHistory(
+            [3, 0, 4, 5, 1, 0, 2]
+                .iter()
+                .enumerate()
+                .map(|(i, &score)| record(i as u32 + 1, score))
+                .collect(),
+        );
+        assert_eq!(history.longest_streak(), 3);
+        assert!(!history.last_wasted());
+        assert_eq!(TurnHistory::default().longest_streak(), 0);
+    }
+
+    #[test]
+    fn last_wasted_checks_the_latest_turn() {
+        let mut history = TurnHistory::default();
+        assert!(!history.last_wasted());
+        history.push(record(1, 3));
+        assert!(!history.last_wasted());
+        history.push(record(2, 0));
+        assert!(history.last_wasted());
+    }
+
+    fn game_over_world(mode: GameMode, history: TurnHistory) -> World {
+        let mut world = World::new();
+        let ball = world
+            .spawn()
+            .insert(ball::Ball)
+            .insert(ball::Species::Red)
+            .id();
+        let mut grid = grid::Grid::default();
+        grid.set(hex::Coord::new(0, 0), Some(ball));
+        grid.live_species.insert(ball::Species::Red);
+        grid.update_bounds();
+        world.insert_resource(grid);
+        world.insert_resource(Lives {
+            max: 3,
+            left: 3,
+            ..Default::default()
+        });
+        world.insert_resource(mode);
+        world.insert_resource(GameplaySettings::default());
+        world.insert_resource(history);
+        world.insert_resource(ReduceMotion(false));
+        world.insert_resource(ShooterPosition::default());
+        world.insert_resource(GameOverFade::default());
+        world.insert_resource(TurnPhase::Resolving);
+        world.insert_resource(State::new(AppState::Gameplay));
+        world.insert_resource(DebugLines::default());
+        world.insert_resource(Events::<SpeciesCleared>::default());
+        world
+    }
+
+    #[test]
+    fn wasted_shot_ends_a_sudden_death_game() {
+        let history = TurnHistory(vec![record(1, 3), record(2, 0)]);
+
+        let mut world = game_over_world(GameMode::SuddenDeath, history.clone());
+        SystemStage::single_threaded()
+            .with_system(check_game_over)
+            .run(&mut world);
+        assert!(world.resource::<GameOverFade>().0.is_some());
+        // no life can save a wasted shot
+        assert_eq!(world.resource::<Lives>().left(), 3);
+
+        let mut world = game_over_world(GameMode::Normal, history);
+        SystemStage::single_threaded()
+            .with_system(check_game_over)
+            .run(&mut world);
+        assert!(world.resource::<GameOverFade>().0.is_none());
+    }
+
+    #[test]
+    fn large_clears_are_despawned_over_several_frames() {
+        use bevy::ecs::system::CommandQueue;
+
+        let mut world = World::new();
+        let mut queue = ClearQueue::default();
+        let small = (0..queue.budget)
+            .map(|_| world.spawn().id())
+            .collect::<Vec<_>>();
+        let large = (0..queue.budget * 3 + 10)
+            .map(|_| world.spawn().id())
+            .collect::<Vec<_>>();
+
+        let mut commands_queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut commands_queue, &world);
+        queue.push(&mut commands, small.clone());
+        assert!(queue.is_empty());
+        queue.push(&mut commands, large.clone());
+        commands_queue.apply(&mut world);
+
+        assert!(small
+            .iter()
+            .all(|entity| world.get_entity(*entity).is_none()));
+        assert!(large
+            .iter()
+            .all(|entity| !world.get::<Visibility>(*entity).unwrap().is_visible));
+
+        world.insert_resource(queue);
+        world.insert_resource(projectile::Paused::default());
+        let mut stage = SystemStage::single_threaded().with_system(drain_clear_queue);
+        for frame in 1..=4 {
+            stage.run(&mut world);
+            let alive = large
+                .iter()
+                .filter(|entity| world.get_entity(**entity).is_some())
+                .count();
+            assert_eq!(alive, large.len().saturating_sub(frame * 64));
+        }
+        assert!(world.resource::<ClearQueue>().is_empty());
+    }
+
+    #[test]
+    fn no_turn_begins_while_the_grid_fades_out() {
+        let mut world = resolving_world();
+        world.resource_mut::<ResolveDelay>().0 = Some(Timer::from_seconds(0.0, false));
+        world.resource_mut::<GameOverFade>().0 = Some(Timer::from_seconds(GAME_OVER_FADE, false));
+
+        let mut stage = SystemStage::single_threaded().with_system(finish_resolving);
+        stage.run(&mut world);
+        assert_eq!(*world.resource::<TurnPhase>(), TurnPhase::Resolving);
+        assert_eq!(turns_begun(&world), 0);
+    }
+
+    #[test]
+    fn game_over_row_follows_the_shooter() {
+        let mut world = game_over_world(GameMode::Normal, TurnHistory::default());
+        let mut stage = SystemStage::single_threaded().with_system(check_game_over);
+        stage.run(&mut world);
+        assert_eq!(world.resource::<Lives>().left(), 3);
+
+        // two rows below the shooter is the top row holding the only ball
+        let layout = world.resource::<grid::Grid>().layout.clone();
+        world.resource_mut::<ShooterPosition>().0 = layout.to_world_y(hex::Coord::new(-1, 2), 0.0);
+        stage.run(&mut world);
+        assert_eq!(world.resource::<Lives>().left(), 2);
+    }
+
+    #[test]
+    fn clearing_keeps_grid_score_and_feedback_in_step() {
+        use bevy::ecs::system::CommandQueue;
+
+        let mut world = World::new();
+        let matched = world.spawn().id();
+        let armored = world.spawn().id();
+        let floating = world.spawn().id();
+        let mut grid = grid::Grid::default();
+        let (a, b, c) = (
+            hex::Coord::new(0, 0),
+            hex::Coord::new(1, 0),
+            hex::Coord::new(0, 1),
+        );
+        grid.set(a, Some(matched));
+        grid.set(b, Some(armored));
+        grid.set(c, Some(floating));
+        grid.health.insert(armored, 2);
+
+        let red = |_: &Entity| Some(ball::Species::Red);
+        let mut clearing = Clearing::default();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        clearing.hit(&mut commands, &mut grid, a, red);
+        clearing.hit(&mut commands, &mut grid, b, red);
+        clearing.drop_ball(&mut grid, c, red);
+        // already gone, doesn't count twice
+        clearing.drop_ball(&mut grid, c, red);
+        queue.apply(&mut world);
+
+        // the armored ball scores its hit but stays on the grid
+        assert_eq!(clearing.score, 3);
+        assert_eq!(clearing.removed, vec![matched, floating]);
+        assert_eq!(
+            clearing.cleared,
+            vec![
+                (grid.layout.to_world(a), Some(ball::Species::Red)),
+                (grid.layout.to_world(c), Some(ball::Species::Red)),
+            ]
+        );
+        assert_eq!(grid.get(b), Some(&armored));
+        assert!(grid.get(a).is_none() && grid.get(c).is_none());
+    }
+
+    #[test]
+    fn settle_sound_is_quieter_on_emptier_boards() {
+        let sound = SettleSound::default();
+        assert_eq!(sound.volume(256, 256), sound.volume);
+        assert_eq!(sound.volume(128, 256), sound.volume * 0.5);
+        assert_eq!(sound.volume(8, 256), sound.volume * 0.25);
+        // overfilled boards don't get any louder
+        assert_eq!(sound.volume(400, 256), sound.volume);
+    }
+
+    #[test]
+    fn losing_a_life_clears_the_bottom_rows() {
+        let mut world = game_over_world(GameMode::Normal, TurnHistory::default());
+        let layout = world.resource::<grid::Grid>().layout.clone();
+        world.resource_mut::<ShooterPosition>().0 = layout.to_world_y(hex::Coord::new(-1, 2), 0.0);
+        let mut stage = SystemStage::single_threaded().with_system(check_game_over);
+
+        stage.run(&mut world);
+        assert_eq!(world.resource::<Lives>().left(), 2);
+        let grid = world.resource::<grid::Grid>();
+        assert!(grid.storage.is_empty());
+        assert!(grid.live_species.is_empty());
+        let events = world.resource::<Events<SpeciesCleared>>();
+        let cleared = events
+            .get_reader()
+            .iter(events)
+            .map(|cleared| cleared.0)
+            .collect::<Vec<_>>();
+        assert_eq!(cleared, vec![ball::Species::Red]);
+
+        // refill the row until the last life is lost
+        for left in [1, 1] {
+            let ball = world
+                .spawn()
+                .insert(ball::Ball)
+                .insert(ball::Species::Red)
+                .id();
+            world
+                .resource_mut::<grid::Grid>()
+                .set(hex::Coord::new(0, 0), Some(ball));
+            stage.run(&mut world);
+            assert_eq!(world.resource::<Lives>().left(), left);
+        }
+        assert!(world.resource::<GameOverFade>().is_running());
+        assert_eq!(world.resource::<grid::Grid>().storage.len(), 1);
+    }
+
+    #[test]
+    fn floating_balls_stay_when_dropping_is_disabled() {
+        // (0, 2) hangs from nothing once its neighbors are gone
+        let mut grid = grid_with(&[(0, 0), (1, 0), (0, 2)]);
+        let red = |_: &Entity| Some(ball::Species::Red);
+
+        let settings = GameplaySettings {
+            drop_floating: false,
+            ..Default::default()
+        };
+        let mut clearing = Clearing::default();
+        clearing.drop_floating(
+            &settings,
+            &mut grid,
+            red,
+            &mut grid::ClusterScratch::default(),
+        );
+        assert_eq!(clearing.score, 0);
+        assert_eq!(grid.storage.len(), 3);
+
+        clearing.drop_floating(
+            &GameplaySettings::default(),
+            &mut grid,
+            red,
+            &mut grid::ClusterScratch::default(),
+        );
+        assert_eq!(clearing.score, 1);
+        assert_eq!(clearing.removed, vec![Entity::from_raw(2)]);
+        assert!(grid.get(hex::Coord::new(0, 2)).is_none());
+    }
+
+    /// Advances the clock by `seconds` and runs `stage`.
+    fn advance(world: &mut World, stage: &mut SystemStage, seconds: f32) {
+        let mut time = world.resource_mut::<Time>();
+        let now = time.last_update().unwrap() + std::time::Duration::from_secs_f32(seconds);
+        time.update_with_instant(now);
+        stage.run(world);
+    }
+
+    #[test]
+    fn timed_descent_moves_the_grid_once_time_runs_out() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .insert_resource(crate::loading::TextureAssets {
+                texture_bevy: Handle::default(),
+            })
+            .init_resource::<ball::SpeciesMesh>()
+            .init_resource::<ball::BallMaterials>();
+        let world = &mut app.world;
+
+        let ball = world.spawn().id();
+        let mut grid = grid::Grid::default();
+        grid.set(hex::Coord::new(0, 0), Some(ball));
+        grid.update_bounds();
+        world.insert_resource(grid);
+        let mut descent_timer = DescentTimer {
+            interval: 5.0,
+            ..Default::default()
+        };
+        descent_timer.restart();
+        world.insert_resource(descent_timer);
+        world.insert_resource(GameMode::Timed);
+        world.insert_resource(TurnPhase::Aiming);
+        world.insert_resource(projectile::Paused::default());
+        world.insert_resource(GameOverFade::default());
+        world.insert_resource(ReduceMotion(false));
+        let mut time = Time::default();
+        time.update_with_instant(bevy::utils::Instant::now());
+        world.insert_resource(time);
+
+        let settings = GameplaySettings::default();
+        let near = |world: &World| {
+            descent_is_near(
+                GameMode::Timed,
+                &settings,
+                1,
+                world.resource::<DescentTimer>(),
+            )
+        };
+        let mut stage = SystemStage::single_threaded().with_system(tick_descent_timer);
+
+        advance(world, &mut stage, 1.0);
+        assert!(!near(world));
+        advance(world, &mut stage, 2.5);
+        assert!(near(world));
+        assert_eq!(
+            world.resource::<grid::Grid>().get(hex::Coord::new(0, 0)),
+            Some(&ball)
+        );
+
+        advance(world, &mut stage, 2.0);
+        let moved = world.resource::<grid::Grid>().get(hex::Coord::new(0, 1));
+        assert_eq!(moved, Some(&ball));
+        assert!(!near(world));
+
+        // no shots until the grid has settled
+        assert!(world.resource::<DescentTimer>().is_sliding());
+        advance(world, &mut stage, grid::DESCENT_DURATION / 2.0);
+        assert!(world.resource::<DescentTimer>().is_sliding());
+        advance(world, &mut stage, grid::DESCENT_DURATION);
+        assert!(!world.resource::<DescentTimer>().is_sliding());
+    }
+}
@@ -1,17 +1,127 @@
 use bevy::{prelude::*, utils::hashbrown::hash_map};
 use bevy_prototype_debug_lines::DebugLines;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
-use crate::loading::TextureAssets;
-
 use super::{
     ball::{self, BallBundle},
-    hex, AppState,
+    gameplay::ShooterPosition,
+    hex,
+    projectile::Paused,
+    save::ResumeGame,
+    theme::Theme,
+    AppState, ReduceMotion,
 };
 
 #[derive(Debug, Copy, Clone)]
 pub struct GenerateGrid(pub i32, pub i32);
 
+/// Settings used when generating a new grid. Insert before adding [GridPlugin] to override the defaults.
+#[derive(Debug, Clone)]
+pub struct GridSettings {
+    /// Number of columns in the initial grid.
+    pub width: i32,
+    /// Number of rows in the initial grid.
+    pub height: i32,
+    /// Inclusive range the number of initial rows is picked from each new game, overrides
+    /// `height` when set.
+    pub random_height: Option<(i32, i32)>,
+    pub orientation: hex::Orientation,
+    /// Size of a single hex, balls and colliders are scaled accordingly. Use different x and y
+    /// sizes to squash or stretch the board.
+    pub hex_size: Vec2,
+    /// World offset of the board, applied after centering it on the x-axis. The projectile
+    /// still spawns at the world origin, so keep the offset within the walls.
+    pub origin_offset: Vec2,
+    /// Cells holding an [ball::Obstacle] instead of a ball in the initial grid.
+    pub obstacles: Vec<hex::Coord>,
+    /// Armored balls in the initial grid and their [ball::Health].
+    pub health: Vec<(hex::Coord, u32)>,
+    /// Projectile species loaded in order at the start of a new game, random species follow
+    /// once it runs out.
+    pub projectiles: Vec<ball::Species>,
+    /// Mirror the left half of a new grid onto the right half, making it symmetric.
+    pub mirrored: bool,
+}
+
+impl GridSettings {
+    /// Returns the number of rows to fill for a new game.
+    pub fn initial_height(&self) -> i32 {
+        match self.random_height {
+            Some((min, max)) => {
+                let (min, max) = (min.min(max), min.max(max));
+                min + (rand::random::<u32>() % (max - min + 1) as u32) as i32
+            }
+            None => self.height,
+        }
+    }
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            width: 16,
+            height: 16,
+            random_height: None,
+            orientation: hex::Orientation::pointy().clone(),
+            hex_size: Vec2::ONE,
+            origin_offset: Vec2::ZERO,
+            obstacles: vec![],
+            health: vec![],
+            projectiles: vec![],
+            mirrored: false,
+        }
+    }
+}
+
+/// Optional puzzle mode hiding the color of balls far from the shooter.
+#[derive(Debug, Clone)]
+pub struct FogOfWar {
+    pub enabled: bool,
+    /// Number of rows, counted from the lowest occupied one, showing their color.
+    pub visible_rows: i32,
+}
+
+impl Default for FogOfWar {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            visible_rows: 4,
+        }
+    }
+}
+
+/// Species of every occupied cell at some point in time.
+pub type GridSnapshot = HashMap<hex::Coord, ball::Species>;
+
+/// Cells that changed between two [GridSnapshot]s. Cells that changed species are both
+/// removed and added.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GridDiff {
+    pub added: Vec<(hex::Coord, ball::Species)>,
+    pub removed: Vec<(hex::Coord, ball::Species)>,
+}
+
+impl GridDiff {
+    /// Computes the changes from `before` to `after`, sorted by coordinate.
+    pub fn between(before: &GridSnapshot, after: &GridSnapshot) -> Self {
+        let changed = |from: &GridSnapshot, to: &GridSnapshot| {
+            let mut cells = from
+                .iter()
+                .filter(|(hex, species)| to.get(*hex) != Some(*species))
+                .map(|(&hex, &species)| (hex, species))
+                .collect::<Vec<_>>();
+            cells.sort_by_key(|(hex, _)| *hex);
+            cells
+        };
+
+        Self {
+            added: changed(after, before),
+            removed: changed(before, after),
+        }
+    }
+}
+
 /// A dynamic hexagonal grid.
 #[derive(Default, Debug, Clone)]
 pub struct Grid {
@@ -21,6 +131,14 @@ pub struct Grid {
     pub bounds: hex::Bounds,
     /// True if bounds haven't been updated since last modification.
     pub dirty: bool,
+    /// Species present on the grid, refreshed after each turn.
+    pub live_species: HashSet<ball::Species>,
+    /// Entities of every [ball::Obstacle] on the grid.
+    pub obstacles: HashSet<Entity>,
+    /// [ball::Health] of every armored ball, balls with a single hit left aren't included.
+    pub health: HashMap<Entity, u32>,
+    /// Species of the row spawned by the next [move_down_and_spawn], generated ahead of time.
+    pub next_row: HashMap<hex::Coord, ball::Species>,
 }
 
 impl Grid {
@@ -32,8 +150,35 @@ impl Grid {
         self.dirty = true;
         match entity {
             Some(entity) => self.storage.insert(hex.clone(), entity),
-            None => self.storage.remove(&hex),
+            None => {
+                let entity = self.storage.remove(&hex);
+                if let Some(entity) = entity {
+                    self.health.remove(&entity);
+                }
+                entity
+            }
+        }
+    }
+
+    /// Clears the ball at `hex`, armored balls lose one health instead. Returns the entity if it
+    /// was removed from the grid, despawning it is left to the caller.
+    pub fn clear_ball(&mut self, commands: &mut Commands, hex: hex::Coord) -> Option<Entity> {
+        let entity = *self.get(hex).unwrap();
+        if let Some(health) = self.health.get_mut(&entity) {
+            *health -= 1;
+            match *health {
+                0 | 1 => {
+                    self.health.remove(&entity);
+                    commands.entity(entity).remove::<ball::Health>();
+                }
+                health => {
+                    commands.entity(entity).insert(ball::Health(health));
+                }
+            }
+            return None;
         }
+
+        self.set(hex, None)
     }
 
     pub fn dim(&self) -> (f32, f32) {
@@ -55,6 +200,16 @@ impl Grid {
         (h / hh / 2.).round() as i32
     }
 
+    /// Returns the ball closest to the row at world `row_z`, which is the one with the largest
+    /// z, and its distance to the row. The distance is negative once the ball is past the row.
+    pub fn closest_to_row(&self, row_z: f32) -> Option<(hex::Coord, f32)> {
+        self.storage
+            .keys()
+            .map(|&hex| (hex, row_z - self.layout.to_world_y(hex, 0.0).z))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Returns the occupied neighbors of `hex`.
     pub fn neighbors(&self, hex: hex::Coord) -> Vec<(hex::Coord, &Entity)> {
         hex.neighbors()
             .iter()
@@ -89,14 +244,62 @@ impl Grid {
         }
     }
 
+    /// Captures the species of every cell, resolved through `species_of`.
+    pub fn snapshot<F>(&self, species_of: F) -> GridSnapshot
+    where
+        F: Fn(&Entity) -> Option<ball::Species>,
+    {
+        self.storage
+            .iter()
+            .filter_map(|(&hex, entity)| species_of(entity).map(|species| (hex, species)))
+            .collect()
+    }
+
+    /// Returns the row spawned by the next [move_down_and_spawn], generating any missing species.
+    pub fn next_row(&mut self) -> Vec<(hex::Coord, ball::Species)> {
+        hex::rectangle(self.columns(), 1, &self.layout)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|hex| {
+                let species = *self
+                    .next_row
+                    .entry(hex)
+                    .or_insert_with(ball::random_species);
+                (hex, species)
+            })
+            .collect()
+    }
+
+    /// Offset from a cell to the cell one row below it in world space.
+    pub fn row_step(&self) -> Vec3 {
+        self.layout.to_world_y(hex::Coord::new(0, 1), 0.0)
+            - self.layout.to_world_y(hex::Coord::new(0, 0), 0.0)
+    }
+
     pub fn clear(&mut self) {
         self.storage.clear();
+        self.live_species.clear();
+        self.obstacles.clear();
+        self.health.clear();
+        self.next_row.clear();
         self.update_bounds();
     }
 }
 
-#[inline(always)]
-pub fn find_cluster<'a, P>(
+/// Buffers reused by cluster searches, so searching a full board on every snap doesn't
+/// allocate them again for each cluster.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterScratch {
+    to_process: Vec<hex::Coord>,
+    /// Cells visited by the last [find_cluster_with].
+    pub processed: HashSet<hex::Coord>,
+    /// Cells already covered by a search over several clusters.
+    seen: HashSet<hex::Coord>,
+}
+
+/// Returns the cluster of connected balls around `origin` matching `is_cluster`, and every
+/// cell visited to find it: the cluster and its occupied neighbors.
+pub fn find_cluster<P>(
     grid: &Grid,
     origin: hex::Coord,
     is_cluster: P,
@@ -104,10 +307,41 @@ pub fn find_cluster<'a, P>(
 where
     P: Fn(&Entity) -> bool,
 {
-    let mut processed = HashSet::<hex::Coord>::new();
-    let mut to_process = vec![origin];
+    let mut scratch = ClusterScratch::default();
+    let cluster = find_cluster_with(grid, origin, is_cluster, &mut scratch);
+    (cluster, scratch.processed)
+}
+
+/// Like [find_cluster], but reuses the buffers in `scratch`. The visited cells are left in
+/// [ClusterScratch::processed].
+#[inline(always)]
+pub fn find_cluster_with<P>(
+    grid: &Grid,
+    origin: hex::Coord,
+    is_cluster: P,
+    scratch: &mut ClusterScratch,
+) -> Vec<hex::Coord>
+where
+    P: Fn(&Entity) -> bool,
+{
+    let processed = &mut scratch.processed;
+    let to_process = &mut scratch.to_process;
+    processed.clear();
+    to_process.clear();
+
+    // a lone ball is the most common case when snapping, skip the search entirely
+    if let Some(entity) = grid.get(origin) {
+        let neighbors = grid.neighbors(origin);
+        if is_cluster(entity) && neighbors.iter().all(|(_, e)| !is_cluster(e)) {
+            processed.extend(neighbors.iter().map(|(hex, _)| *hex));
+            processed.insert(origin);
+            return vec![origin];
+        }
+    }
+
     let mut cluster: Vec<hex::Coord> = vec![];
 
+    to_process.push(origin);
     processed.insert(origin);
 
     while let Some(current) = to_process.pop() {
@@ -128,12 +362,71 @@ where
         }
     }
 
-    (cluster, processed)
+    cluster
+}
+
+/// Finds every cluster of at least `min_size` connected balls sharing the same species,
+/// where `species_of` resolves the species of a grid entity.
+pub fn find_matching_clusters<F>(
+    grid: &Grid,
+    min_size: usize,
+    species_of: F,
+    scratch: &mut ClusterScratch,
+) -> Vec<Vec<hex::Coord>>
+where
+    F: Fn(&Entity) -> Option<ball::Species>,
+{
+    find_matching_clusters_from(
+        grid,
+        grid.storage.keys().copied(),
+        min_size,
+        species_of,
+        scratch,
+    )
+}
+
+/// Like [find_matching_clusters], but only searches the clusters containing one of `seeds`.
+/// Each cluster is returned once, even if several seeds fall within it.
+pub fn find_matching_clusters_from<F>(
+    grid: &Grid,
+    seeds: impl IntoIterator<Item = hex::Coord>,
+    min_size: usize,
+    species_of: F,
+    scratch: &mut ClusterScratch,
+) -> Vec<Vec<hex::Coord>>
+where
+    F: Fn(&Entity) -> Option<ball::Species>,
+{
+    let mut matched = std::mem::take(&mut scratch.seen);
+    matched.clear();
+    let mut clusters: Vec<Vec<hex::Coord>> = vec![];
+
+    for hex in seeds {
+        if matched.contains(&hex) {
+            continue;
+        }
+
+        let species = match grid.get(hex).and_then(|entity| species_of(entity)) {
+            Some(species) => species,
+            None => continue,
+        };
+
+        let cluster = find_cluster_with(grid, hex, |e| species_of(e) == Some(species), scratch);
+
+        matched.extend(cluster.iter());
+
+        if cluster.len() >= min_size {
+            clusters.push(cluster);
+        }
+    }
+    scratch.seen = matched;
+    clusters
 }
 
 #[inline(always)]
-pub fn find_floating_clusters(grid: &Grid) -> Vec<Vec<hex::Coord>> {
-    let mut processed = HashSet::<hex::Coord>::new();
+pub fn find_floating_clusters(grid: &Grid, scratch: &mut ClusterScratch) -> Vec<Vec<hex::Coord>> {
+    let mut processed = std::mem::take(&mut scratch.seen);
+    processed.clear();
     let mut floating_clusters: Vec<Vec<hex::Coord>> = vec![];
 
     for (hex, _) in grid.storage.iter() {
@@ -141,9 +434,9 @@ pub fn find_floating_clusters(grid: &Grid) -> Vec<Vec<hex::Coord>> {
             continue;
         }
 
-        let (cluster, _processed) = find_cluster(grid, *hex, |_| true);
+        let cluster = find_cluster_with(grid, *hex, |_| true, scratch);
 
-        processed.extend(_processed);
+        processed.extend(scratch.processed.iter());
 
         if cluster.len() <= 0 {
             continue;
@@ -156,21 +449,118 @@ pub fn find_floating_clusters(grid: &Grid) -> Vec<Vec<hex::Coord>> {
                 floating = false;
                 break;
             }
+
+            // obstacles anchor everything resting on them
+            if grid.obstacles.contains(grid.get(*hex).unwrap()) {
+                floating = false;
+                break;
+            }
         }
         if floating {
             floating_clusters.push(cluster);
         }
     }
+    scratch.seen = processed;
     floating_clusters
 }
 
+/// Returns the balls that would start floating if `removed` were cleared, without touching
+/// `grid`. Balls that are already floating aren't included.
+pub fn floating_if_removed(grid: &Grid, removed: &[hex::Coord]) -> Vec<hex::Coord> {
+    let mut scratch = ClusterScratch::default();
+    let already: HashSet<hex::Coord> = find_floating_clusters(grid, &mut scratch)
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let view = Grid {
+        layout: grid.layout.clone(),
+        storage: grid
+            .storage
+            .iter()
+            .filter(|(hex, _)| !removed.contains(hex))
+            .map(|(&hex, &entity)| (hex, entity))
+            .collect(),
+        obstacles: grid.obstacles.clone(),
+        ..Default::default()
+    };
+
+    find_floating_clusters(&view, &mut scratch)
+        .into_iter()
+        .flatten()
+        .filter(|hex| !already.contains(hex))
+        .collect()
+}
+
+/// Lets balls settle into empty cells below them, lowest rows first. Balls never move
+/// below the lowest occupied row, the top row and obstacles stay in place. Returns the moved
+/// entities and their new coordinates, storage is updated in place.
+pub fn compact_down(grid: &mut Grid) -> Vec<(Entity, hex::Coord)> {
+    let floor = match grid.storage.keys().map(|hex| hex.r).max() {
+        Some(floor) => floor,
+        None => return vec![],
+    };
+
+    let mut moved: HashMap<Entity, hex::Coord> = HashMap::new();
+    loop {
+        let mut hexes = grid.storage.keys().copied().collect::<Vec<_>>();
+        hexes.sort_by_key(|hex| std::cmp::Reverse(hex.r));
+
+        let mut settled = true;
+        for hex in hexes {
+            if hex.r == 0 || hex.r >= floor || grid.obstacles.contains(grid.get(hex).unwrap()) {
+                continue;
+            }
+
+            // prefer the cell straight below, same as when moving the grid down
+            let dirs: &[hex::Direction] = match grid.layout.is_pointy() {
+                true => match hex.r % 2 == 0 {
+                    true => &[hex::Direction::F, hex::Direction::E],
+                    false => &[hex::Direction::E, hex::Direction::F],
+                },
+                false => &[hex::Direction::F],
+            };
+
+            if let Some(down) = dirs
+                .iter()
+                .map(|&dir| hex.neighbor(dir))
+                .find(|down| grid.get(*down).is_none())
+            {
+                let entity = grid.storage.remove(&hex).unwrap();
+                grid.set(down, Some(entity));
+                moved.insert(entity, down);
+                settled = false;
+            }
+        }
+
+        if settled {
+            break;
+        }
+    }
+    moved.into_iter().collect()
+}
+
+/// Seconds it takes the grid to slide down one row.
+pub const DESCENT_DURATION: f32 = 0.3;
+
+/// Slides a ball from `from` to the world position of its [hex::Coord].
+#[derive(Component, Debug, Clone)]
+pub struct Slide {
+    pub from: Vec3,
+    pub elapsed: f32,
+}
+
+impl Slide {
+    pub fn new(from: Vec3) -> Self {
+        Self { from, elapsed: 0.0 }
+    }
+}
+
 pub fn move_down_and_spawn(
     commands: &mut Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    assets: &mut ball::BallAssets,
     grid: &mut Grid,
-    texture_assets: &Res<TextureAssets>,
-) {
+) -> Vec<(Entity, ball::Species)> {
     let mut hash_map: HashMap<hex::Coord, Option<&Entity>> = HashMap::new();
     for (&hex, entity) in grid.storage.iter() {
         let dir = match grid.layout.is_pointy() {
@@ -182,7 +572,10 @@ pub fn move_down_and_spawn(
         };
 
         let down = hex.neighbor(dir);
-        commands.entity(*entity).insert(down);
+        commands
+            .entity(*entity)
+            .insert(down)
+            .insert(Slide::new(grid.layout.to_world_y(hex, 0.0)));
         hash_map.insert(down, Some(entity));
     }
 
@@ -191,31 +584,81 @@ pub fn move_down_and_spawn(
         .map(|(&hex, &entity)| (hex, entity.unwrap().clone()))
         .collect();
 
-    for hex in hex::rectangle(grid.columns(), 1, &grid.layout) {
+    // new balls slide in from one row above the grid
+    let row_step = grid.row_step();
+
+    let mut spawned = vec![];
+    for (hex, species) in grid.next_row() {
         let world_pos = grid.layout.to_world_y(hex, 0.0);
         let ball = commands
             .spawn_bundle(BallBundle::new(
                 world_pos,
-                grid.layout.size.x,
-                ball::random_species(),
-                &mut meshes,
-                &mut materials,
-                texture_assets,
+                grid.layout.radius(),
+                species,
+                assets,
             ))
             .insert(hex)
+            .insert(Slide::new(world_pos - Vec3::Z * row_step.z))
             .id();
 
         grid.set(hex, Some(ball));
+        spawned.push((ball, species));
     }
+    grid.next_row.clear();
+    spawned
+}
+
+/// Returns the cells of balls touching a ball they didn't touch in `before`, including balls
+/// that weren't on the grid yet. Moving down shifts every other row sideways, so
+/// [move_down_and_spawn] can bring balls into contact anywhere on the grid.
+pub fn new_contacts(before: &HashMap<hex::Coord, Entity>, grid: &Grid) -> Vec<hex::Coord> {
+    let touching = |storage: &HashMap<hex::Coord, Entity>, hex: hex::Coord| {
+        hex.neighbors()
+            .iter()
+            .filter_map(|hex| storage.get(hex).copied())
+            .collect::<HashSet<_>>()
+    };
+    let moved_from = before
+        .iter()
+        .map(|(&hex, &entity)| (entity, hex))
+        .collect::<HashMap<_, _>>();
+
+    grid.storage
+        .iter()
+        .filter(|(&hex, entity)| match moved_from.get(*entity) {
+            Some(&from) => !touching(&grid.storage, hex).is_subset(&touching(before, from)),
+            None => true,
+        })
+        .map(|(&hex, _)| hex)
+        .collect()
+}
+
+/// Picks a random species for every cell of a new grid, see [GridSettings::mirrored].
+fn random_balls(settings: &GridSettings, layout: &hex::Layout) -> Vec<(hex::Coord, ball::Species)> {
+    let mut balls = HashMap::new();
+    hex::rectangle(settings.width, settings.initial_height(), layout)
+        .filter(|hex| !settings.obstacles.contains(hex))
+        .map(|hex| {
+            let mirrored = match settings.mirrored {
+                true => balls
+                    .get(&hex::mirror(hex, settings.width, layout))
+                    .copied(),
+                false => None,
+            };
+            let species = mirrored.unwrap_or_else(ball::random_species);
+            balls.insert(hex, species);
+            (hex, species)
+        })
+        .collect()
 }
 
 fn generate_grid(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: ball::BallAssets,
     mut grid: ResMut<Grid>,
     hexes: Query<Entity, With<hex::Coord>>,
-    texture_assets: Res<TextureAssets>,
+    settings: Res<GridSettings>,
+    resume: Res<ResumeGame>,
 ) {
     for entity in hexes.iter() {
         commands.entity(entity).despawn();
@@ -223,37 +666,87 @@ fn generate_grid(
 
     grid.clear();
 
-    const WIDTH: i32 = 16;
-    const HEIGHT: i32 = 16;
+    grid.layout.orientation = settings.orientation.clone();
+    grid.layout.size = settings.hex_size;
+    grid.layout.origin = Vec2::ZERO;
 
-    for hex in hex::rectangle(WIDTH, HEIGHT, &grid.layout) {
+    let (balls, obstacles, health): (
+        Vec<(hex::Coord, ball::Species)>,
+        Vec<hex::Coord>,
+        Vec<(hex::Coord, u32)>,
+    ) = match &resume.0 {
+        Some(saved) => {
+            grid.layout.origin = Vec2::from(saved.origin);
+            (
+                saved.balls.clone(),
+                saved.obstacles.clone(),
+                saved.health.clone(),
+            )
+        }
+        None => (
+            random_balls(&settings, &grid.layout),
+            settings.obstacles.clone(),
+            settings.health.clone(),
+        ),
+    };
+
+    for hex in obstacles {
+        let world_pos = grid.layout.to_world_y(hex, 0.0);
+        let entity = commands
+            .spawn_bundle(ball::ObstacleBundle::new(
+                world_pos,
+                grid.layout.radius(),
+                &mut assets.meshes,
+                &mut assets.materials,
+            ))
+            .insert(hex)
+            .id();
+
+        grid.set(hex, Some(entity));
+        grid.obstacles.insert(entity);
+    }
+
+    for (hex, species) in balls {
         let world_pos = grid.layout.to_world_y(hex, 0.0);
         let entity = commands
             .spawn_bundle(BallBundle::new(
                 world_pos,
-                grid.layout.size.x,
-                ball::random_species(),
-                &mut meshes,
-                &mut materials,
-                &texture_assets,
+                grid.layout.radius(),
+                species,
+                &mut assets,
             ))
             .insert(hex)
             .id();
 
         grid.set(hex, Some(entity));
+        grid.live_species.insert(species);
+    }
+
+    for (hex, health) in health.into_iter().filter(|&(_, health)| health > 1) {
+        if let Some(&entity) = grid.get(hex) {
+            if grid.obstacles.contains(&entity) {
+                continue;
+            }
+            commands.entity(entity).insert(ball::Health(health));
+            grid.health.insert(entity, health);
+        }
     }
 
     grid.update_bounds();
 
-    // Center grid on x-axis.
+    if resume.0.is_some() {
+        return;
+    }
+
+    // Center grid on x-axis, then move it by the configured offset.
     let (width, _) = grid.dim();
-    grid.layout.origin.x = -width / 2.;
+    grid.layout.origin = Vec2::new(-width / 2., 0.) + settings.origin_offset;
 
     grid.update_bounds();
 }
 
 fn update_hex_coord_transforms(
-    mut hexes: Query<(Entity, &mut Transform, &hex::Coord), Changed<hex::Coord>>,
+    mut hexes: Query<(Entity, &mut Transform, &hex::Coord), (Changed<hex::Coord>, Without<Slide>)>,
     mut grid: ResMut<Grid>,
 ) {
     for (entity, mut transform, hex) in hexes.iter_mut() {
@@ -264,6 +757,255 @@ fn update_hex_coord_transforms(
     }
 }
 
+/// Sent once every ball has finished sliding after [move_down_and_spawn].
+pub struct DescentSettled {
+    /// Number of balls that moved.
+    pub balls: usize,
+}
+
+fn animate_slides(
+    mut commands: Commands,
+    mut slides: Query<(Entity, &mut Transform, &hex::Coord, &mut Slide)>,
+    mut descent_settled: EventWriter<DescentSettled>,
+    grid: Res<Grid>,
+    reduce_motion: Res<ReduceMotion>,
+    paused: Res<Paused>,
+    time: Res<Time>,
+) {
+    if paused.0 {
+        return;
+    }
+
+    let sliding = slides.iter().count();
+    let mut settled = 0;
+    for (entity, mut transform, hex, mut slide) in slides.iter_mut() {
+        slide.elapsed += time.delta_seconds();
+        let t = match reduce_motion.0 {
+            true => 1.0,
+            false => (slide.elapsed / DESCENT_DURATION).min(1.0),
+        };
+
+        let target = grid.layout.to_world_y(*hex, slide.from.y);
+        transform.translation = slide.from.lerp(target, t);
+
+        if t >= 1.0 {
+            commands.entity(entity).remove::<Slide>();
+            settled += 1;
+        }
+    }
+
+    if settled > 0 && settled == sliding {
+        descent_settled.send(DescentSettled { balls: settled });
+    }
+}
+
+fn update_health_materials(
+    mut armored: Query<
+        (&mut Handle<StandardMaterial>, &ball::Species, &ball::Health),
+        Changed<ball::Health>,
+    >,
+    unarmored: RemovedComponents<ball::Health>,
+    mut balls: Query<(&mut Handle<StandardMaterial>, &ball::Species), Without<ball::Health>>,
+    mut assets: ball::BallAssets,
+) {
+    for (mut handle, species, health) in armored.iter_mut() {
+        *handle = assets.material(ball::health_color(*species, health.0));
+    }
+
+    for entity in unarmored.iter() {
+        if let Ok((mut handle, species)) = balls.get_mut(entity) {
+            *handle = assets.material(ball::species_to_color(*species));
+        }
+    }
+}
+
+/// Ring drawn around an armored ball, following it until it loses its [ball::Health].
+#[derive(Component)]
+struct HealthRing(Entity);
+
+/// Width of a [HealthRing] per clear the ball absorbs beyond the last one.
+const HEALTH_RING_WIDTH: f32 = 0.08;
+
+/// Meshes and material shared by every [HealthRing], meshes keyed by health and hex size.
+#[derive(Default)]
+struct HealthRingAssets {
+    meshes: HashMap<(u32, [u32; 2]), Handle<Mesh>>,
+    material: Option<Handle<StandardMaterial>>,
+}
+
+/// Rings armored balls with an outline of their hex, thicker the more health they have left.
+fn update_health_rings(
+    mut commands: Commands,
+    armored: Query<(Entity, &Transform, &ball::Health), Without<HealthRing>>,
+    mut rings: Query<(Entity, &HealthRing, &mut Transform, &mut Handle<Mesh>)>,
+    grid: Res<Grid>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut cache: Local<HealthRingAssets>,
+) {
+    let size = grid.layout.size.to_array().map(f32::to_bits);
+    let mut mesh = |health: u32| {
+        cache
+            .meshes
+            .entry((health, size))
+            .or_insert_with(|| {
+                let thickness = HEALTH_RING_WIDTH * health.saturating_sub(1) as f32;
+                meshes.add(grid.layout.outline_mesh(thickness))
+            })
+            .clone()
+    };
+
+    let mut ringed = HashSet::new();
+    for (entity, ring, mut transform, mut handle) in rings.iter_mut() {
+        match armored.get(ring.0) {
+            Ok((ball, ball_transform, health)) => {
+                // follows the ball while it slides down
+                transform.translation = ball_transform.translation;
+                let health_mesh = mesh(health.0);
+                if *handle != health_mesh {
+                    *handle = health_mesh;
+                }
+                ringed.insert(ball);
+            }
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+
+    let unringed = armored
+        .iter()
+        .filter(|(entity, _, _)| !ringed.contains(entity))
+        .map(|(entity, transform, health)| (entity, transform.translation, mesh(health.0)))
+        .collect::<Vec<_>>();
+    if unringed.is_empty() {
+        return;
+    }
+
+    let material = cache
+        .material
+        .get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: ball::ARMOR_COLOR,
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            })
+        })
+        .clone();
+    for (entity, translation, mesh) in unringed {
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            })
+            .insert(HealthRing(entity));
+    }
+}
+
+/// Grays out balls hidden by [FogOfWar], revealing them as the grid descends or is cleared.
+fn update_fog_of_war(
+    fog: Res<FogOfWar>,
+    grid: Res<Grid>,
+    mut balls: Query<(
+        &hex::Coord,
+        &ball::Species,
+        &mut Handle<StandardMaterial>,
+        Option<&ball::Health>,
+    )>,
+    mut assets: ball::BallAssets,
+) {
+    if !fog.enabled {
+        return;
+    }
+
+    let lowest = match grid.storage.keys().map(|hex| hex.r).max() {
+        Some(lowest) => lowest,
+        None => return,
+    };
+
+    for (hex, species, mut handle, health) in balls.iter_mut() {
+        let color = match lowest - hex.r < fog.visible_rows {
+            true => ball::health_color(*species, health.map_or(1, |health| health.0)),
+            false => ball::FOG_COLOR,
+        };
+        let material = assets.material(color);
+        // avoid flagging every ball as changed each frame
+        if *handle != material {
+            *handle = material;
+        }
+    }
+}
+
+/// Key used to save a [render_snapshot] of the grid to `snapshot.png`.
+#[cfg(not(target_arch = "wasm32"))]
+pub const SNAPSHOT_KEY: KeyCode = KeyCode::F10;
+
+/// Draws every cell of `snapshot` as a ball colored by species, `scale` being the number of
+/// pixels per world unit. Runs on the CPU, used for level thumbnails and bug reports.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_snapshot(
+    snapshot: &GridSnapshot,
+    layout: &hex::Layout,
+    scale: f32,
+) -> image::RgbaImage {
+    let bounds = snapshot
+        .keys()
+        .map(|&hex| layout.hex_rect_bounds(hex))
+        .reduce(|a, b| hex::Bounds {
+            mins: a.mins.min(b.mins),
+            maxs: a.maxs.max(b.maxs),
+        });
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return image::RgbaImage::new(1, 1),
+    };
+
+    let size = ((bounds.maxs - bounds.mins) * scale).ceil();
+    let mut image = image::RgbaImage::new(size.x as u32, size.y as u32);
+    let radius = layout.radius() * ball::BALL_RADIUS_COEFF * scale;
+
+    for (&hex, &species) in snapshot.iter() {
+        let center = (layout.to_world(hex) - bounds.mins) * scale;
+        let color = image::Rgba(
+            ball::species_to_color(species)
+                .as_rgba_f32()
+                .map(|channel| (channel * 255.0) as u8),
+        );
+        let mins = (center - radius).floor().max(Vec2::ZERO);
+        let maxs = (center + radius).ceil().min(size);
+        for y in mins.y as u32..maxs.y as u32 {
+            for x in mins.x as u32..maxs.x as u32 {
+                let pixel = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if pixel.distance_squared(center) <= radius * radius {
+                    image.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    image
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_snapshot(
+    keys: Res<Input<KeyCode>>,
+    grid: Res<Grid>,
+    balls: Query<&ball::Species, With<ball::Ball>>,
+) {
+    if !keys.just_pressed(SNAPSHOT_KEY) {
+        return;
+    }
+
+    let path = "snapshot.png";
+    let snapshot = grid.snapshot(|entity| balls.get(*entity).ok().copied());
+    match render_snapshot(&snapshot, &grid.layout, 32.0).save(path) {
+        Ok(()) => info!("saved grid snapshot to {}", path),
+        Err(err) => warn!("failed to save grid snapshot: {}", err),
+    }
+}
+
 fn display_grid_bounds(grid: Res<Grid>, mut lines: ResMut<DebugLines>) {
     const Z_LENGTH: f32 = 1000.;
 
@@ -282,10 +1024,70 @@ fn display_grid_bounds(grid: Res<Grid>, mut lines: ResMut<DebugLines>) {
     );
 }
 
+/// Plane behind the grid and the shooter, see [Theme::board].
+#[derive(Component)]
+struct Board;
+
+/// Height of the [Board], just below the balls.
+const BOARD_Y: f32 = -1.0;
+
+/// How far the [Board] extends past the shooter.
+const BOARD_MARGIN: f32 = 2.0;
+
+fn setup_board(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+) {
+    let color = match theme.board {
+        Some(color) => color,
+        None => return,
+    };
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Plane { size: 1.0 })),
+            material: materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                ..default()
+            }),
+            ..default()
+        })
+        .insert(Board);
+}
+
+/// Fits the [Board] to the grid bounds, extended down past the shooter.
+fn fit_board(
+    grid: Res<Grid>,
+    shooter: Res<ShooterPosition>,
+    mut board: Query<&mut Transform, With<Board>>,
+    added: Query<(), Added<Board>>,
+) {
+    if !grid.is_changed() && !shooter.is_changed() && added.is_empty() {
+        return;
+    }
+
+    let (min_x, max_x) = (grid.bounds.mins.x, grid.bounds.maxs.x);
+    let min_z = grid.bounds.mins.y;
+    let max_z = grid.bounds.maxs.y.max(shooter.z + BOARD_MARGIN);
+    for mut transform in board.iter_mut() {
+        transform.translation = Vec3::new((min_x + max_x) / 2., BOARD_Y, (min_z + max_z) / 2.);
+        transform.scale = Vec3::new(max_x - min_x, 1., max_z - min_z);
+    }
+}
+
+fn cleanup_board(mut commands: Commands, board: Query<Entity, With<Board>>) {
+    for entity in board.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
 fn cleanup_grid(
     mut commands: Commands,
     mut grid: ResMut<Grid>,
-    hexes: Query<Entity, With<hex::Coord>>,
+    hexes: Query<Entity, Or<(With<hex::Coord>, With<HealthRing>)>>,
 ) {
     for entity in hexes.iter() {
         commands.entity(entity).despawn();
@@ -305,13 +1107,499 @@ impl Plugin for GridPlugin {
             },
             ..Default::default()
         });
-        app.add_system_set(SystemSet::on_enter(AppState::Gameplay).with_system(generate_grid));
+        app.add_event::<DescentSettled>();
+        app.init_resource::<GridSettings>();
+        app.init_resource::<ClusterScratch>();
+        app.init_resource::<FogOfWar>();
+        app.init_resource::<ball::SpeciesMesh>();
+        app.init_resource::<ball::BallMaterials>();
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Gameplay)
+                .with_system(generate_grid)
+                .with_system(setup_board),
+        );
         app.add_system_set(
-            SystemSet::on_update(AppState::Gameplay).with_system(update_hex_coord_transforms),
+            SystemSet::on_update(AppState::Gameplay)
+                .with_system(update_hex_coord_transforms)
+                .with_system(animate_slides)
+                .with_system(update_health_materials)
+                .with_system(update_health_rings)
+                .with_system(update_fog_of_war.after(update_health_materials))
+                .with_system(fit_board),
         );
         app.add_system_set(
             SystemSet::on_update(AppState::Gameplay).with_system(display_grid_bounds),
         );
-        app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(cleanup_grid));
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_system_set(SystemSet::on_update(AppState::Gameplay).with_system(save_snapshot));
+        app.add_system_set(
+            SystemSet::on_exit(AppState::Gameplay)
+                .with_system(cleanup_grid)
+                .with_system(cleanup_board),
+        );
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a grid with a placeholder entity in each of `hexes`, numbered in order.
+    pub(crate) fn grid_with(hexes: &[(i32, i32)]) -> Grid {
+        let mut grid = Grid::default();
+        for (i, &(q, r)) in hexes.iter().enumerate() {
+            grid.set(hex::Coord::new(q, r), Some(Entity::from_raw(i as u32)));
+        }
+        grid
+    }
+
+    fn sorted(mut hexes: Vec<hex::Coord>) -> Vec<hex::Coord> {
+        hexes.sort();
+        hexes
+    }
+
+    #[test]
+    fn center_has_six_occupied_neighbors() {
+        let center = hex::Coord::new(2, 2);
+        let mut hexes = center
+            .neighbors()
+            .iter()
+            .map(|hex| (hex.q, hex.r))
+            .collect::<Vec<_>>();
+        // the center itself and cells further out
+        hexes.extend([(2, 2), (5, 2), (2, 5)]);
+        let grid = grid_with(&hexes);
+        assert!(grid.layout.is_pointy());
+
+        let neighbors = sorted(grid.neighbors(center).iter().map(|(hex, _)| *hex).collect());
+        assert_eq!(neighbors.len(), 6);
+        assert_eq!(neighbors, sorted(center.neighbors().to_vec()));
+    }
+
+    #[test]
+    fn hanging_cluster_is_floating() {
+        let grid = grid_with(&[(0, 0), (0, 1), (5, 3), (5, 4)]);
+        let floating = find_floating_clusters(&grid, &mut ClusterScratch::default());
+        assert_eq!(floating.len(), 1);
+        assert_eq!(
+            sorted(floating[0].clone()),
+            vec![hex::Coord::new(5, 3), hex::Coord::new(5, 4)]
+        );
+    }
+
+    #[test]
+    fn ceiling_anchored_cluster_is_not_floating() {
+        let grid = grid_with(&[(0, 0), (0, 1), (0, 2), (0, 3), (-1, 4)]);
+        assert!(find_floating_clusters(&grid, &mut ClusterScratch::default()).is_empty());
+    }
+
+    #[test]
+    fn bridge_is_anchored_by_either_end() {
+        // (0, 1) through (3, 1) only reach the top row through (4, 0)
+        let bridge = [(0, 1), (1, 1), (2, 1), (3, 1), (4, 0)];
+        assert!(
+            find_floating_clusters(&grid_with(&bridge), &mut ClusterScratch::default()).is_empty()
+        );
+
+        // cutting the bridge drops everything left of the cut
+        let cut = [(0, 1), (1, 1), (2, 1), (4, 0)];
+        let floating = find_floating_clusters(&grid_with(&cut), &mut ClusterScratch::default());
+        assert_eq!(floating.len(), 1);
+        assert_eq!(
+            sorted(floating[0].clone()),
+            vec![
+                hex::Coord::new(0, 1),
+                hex::Coord::new(1, 1),
+                hex::Coord::new(2, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn obstacles_anchor_clusters() {
+        let mut grid = grid_with(&[(5, 3), (5, 4)]);
+        grid.obstacles.insert(Entity::from_raw(0));
+        assert!(find_floating_clusters(&grid, &mut ClusterScratch::default()).is_empty());
+    }
+
+    #[test]
+    fn diff_of_a_clearing_shot() {
+        use ball::Species::*;
+
+        let before = GridSnapshot::from([
+            (hex::Coord::new(0, 0), Red),
+            (hex::Coord::new(1, 0), Red),
+            (hex::Coord::new(0, 1), Blue),
+        ]);
+        // the reds are cleared and (0, 1) changes species
+        let after = GridSnapshot::from([(hex::Coord::new(0, 1), Green)]);
+
+        assert_eq!(
+            GridDiff::between(&before, &after),
+            GridDiff {
+                added: vec![(hex::Coord::new(0, 1), Green)],
+                removed: vec![
+                    (hex::Coord::new(0, 0), Red),
+                    (hex::Coord::new(0, 1), Blue),
+                    (hex::Coord::new(1, 0), Red),
+                ],
+            }
+        );
+        assert_eq!(GridDiff::between(&after, &after), GridDiff::default());
+    }
+
+    #[test]
+    fn compact_down_settles_a_column() {
+        // (4, 3) sets the floor, (0, 0) is on the top row
+        let mut grid = grid_with(&[(0, 0), (0, 1), (4, 3)]);
+        let moved = compact_down(&mut grid);
+
+        assert_eq!(moved, vec![(Entity::from_raw(1), hex::Coord::new(-1, 3))]);
+        assert_eq!(
+            sorted(grid.storage.keys().copied().collect()),
+            vec![
+                hex::Coord::new(-1, 3),
+                hex::Coord::new(0, 0),
+                hex::Coord::new(4, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_down_keeps_obstacles_in_place() {
+        let mut grid = grid_with(&[(0, 1), (4, 3)]);
+        grid.obstacles.insert(Entity::from_raw(0));
+        assert!(compact_down(&mut grid).is_empty());
+    }
+
+    #[test]
+    fn obstacles_never_match() {
+        // red, obstacle, red in a row
+        let mut grid = grid_with(&[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        let obstacle = Entity::from_raw(1);
+        grid.obstacles.insert(obstacle);
+        let species_of = |entity: &Entity| match *entity == obstacle {
+            true => None,
+            false => Some(ball::Species::Red),
+        };
+
+        let clusters = find_matching_clusters(&grid, 2, species_of, &mut ClusterScratch::default());
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            sorted(clusters[0].clone()),
+            vec![hex::Coord::new(2, 0), hex::Coord::new(3, 0)]
+        );
+    }
+
+    #[test]
+    fn min_cluster_size_is_configurable() {
+        use crate::gameplay::GameplaySettings;
+
+        let grid = grid_with(&[(0, 0), (1, 0), (2, 0)]);
+        let red = |_: &Entity| Some(ball::Species::Red);
+        let mut scratch = ClusterScratch::default();
+
+        let settings = GameplaySettings {
+            min_cluster_size: 4,
+            ..Default::default()
+        };
+        let clusters =
+            find_matching_clusters(&grid, settings.min_cluster_size(), red, &mut scratch);
+        assert!(clusters.is_empty());
+
+        let settings = GameplaySettings::default();
+        let clusters =
+            find_matching_clusters(&grid, settings.min_cluster_size(), red, &mut scratch);
+        assert_eq!(clusters.len(), 1);
+    }
+
+    #[test]
+    fn lone_obstacles_do_not_float() {
+        let mut grid = grid_with(&[(0, 0), (3, 3)]);
+        grid.obstacles.insert(Entity::from_raw(1));
+        assert!(find_floating_clusters(&grid, &mut ClusterScratch::default()).is_empty());
+    }
+
+    #[test]
+    fn armored_ball_survives_one_clear() {
+        use bevy::ecs::system::CommandQueue;
+
+        let mut world = World::new();
+        let entity = world.spawn().insert(ball::Health(2)).id();
+        let hex = hex::Coord::new(0, 0);
+        let mut grid = Grid::default();
+        grid.set(hex, Some(entity));
+        grid.health.insert(entity, 2);
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        assert_eq!(grid.clear_ball(&mut commands, hex), None);
+        assert_eq!(grid.get(hex), Some(&entity));
+        assert_eq!(grid.clear_ball(&mut commands, hex), Some(entity));
+        assert_eq!(grid.get(hex), None);
+
+        queue.apply(&mut world);
+        assert!(world.get::<ball::Health>(entity).is_none());
+    }
+
+    #[test]
+    fn armored_ball_takes_a_hit_per_health() {
+        use bevy::ecs::system::CommandQueue;
+
+        let mut world = World::new();
+        let entity = world.spawn().insert(ball::Health(3)).id();
+        let hex = hex::Coord::new(0, 0);
+        let mut grid = Grid::default();
+        grid.set(hex, Some(entity));
+        grid.health.insert(entity, 3);
+
+        let hit = |grid: &mut Grid, world: &mut World| {
+            let mut queue = CommandQueue::default();
+            let removed = grid.clear_ball(&mut Commands::new(&mut queue, world), hex);
+            queue.apply(world);
+            (
+                removed,
+                world.get::<ball::Health>(entity).map(|health| health.0),
+            )
+        };
+
+        assert_eq!(hit(&mut grid, &mut world), (None, Some(2)));
+        assert_eq!(grid.health.get(&entity), Some(&2));
+        assert_eq!(hit(&mut grid, &mut world), (None, None));
+        assert!(grid.health.is_empty());
+        assert_eq!(hit(&mut grid, &mut world), (Some(entity), None));
+        assert!(grid.storage.is_empty());
+    }
+
+    #[test]
+    fn chain_drops_when_its_anchor_is_removed() {
+        // (0, 1) holds up the rest of the chain, (6, 4) is already floating
+        let grid = grid_with(&[(0, 0), (0, 1), (0, 2), (0, 3), (-1, 4), (6, 4)]);
+
+        assert_eq!(
+            sorted(floating_if_removed(&grid, &[hex::Coord::new(0, 1)])),
+            vec![
+                hex::Coord::new(-1, 4),
+                hex::Coord::new(0, 2),
+                hex::Coord::new(0, 3)
+            ]
+        );
+        assert_eq!(
+            floating_if_removed(&grid, &[hex::Coord::new(0, 3)]),
+            vec![hex::Coord::new(-1, 4)]
+        );
+        // the grid itself is left untouched
+        assert_eq!(grid.storage.len(), 6);
+    }
+
+    #[test]
+    fn closest_to_row_is_the_lowest_ball() {
+        let grid = grid_with(&[(0, 0), (1, 1), (-1, 3), (2, 2)]);
+        let lowest = hex::Coord::new(-1, 3);
+        let lowest_z = grid.layout.to_world_y(lowest, 0.0).z;
+
+        let (hex, distance) = grid.closest_to_row(lowest_z + 2.0).unwrap();
+        assert_eq!(hex, lowest);
+        assert!((distance - 2.0).abs() < 1e-4);
+
+        // past the row
+        let (_, distance) = grid.closest_to_row(lowest_z - 1.0).unwrap();
+        assert!((distance + 1.0).abs() < 1e-4);
+
+        assert!(Grid::default().closest_to_row(0.0).is_none());
+    }
+
+    #[test]
+    fn matching_from_disconnected_seeds() {
+        let grid = grid_with(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (5, 5),
+            (6, 5),
+            (7, 5),
+        ]);
+        let red = |_: &Entity| Some(ball::Species::Red);
+
+        // two seeds in the first cluster, one in the second and an empty cell
+        let seeds = [
+            hex::Coord::new(0, 0),
+            hex::Coord::new(2, 0),
+            hex::Coord::new(1, 3),
+            hex::Coord::new(9, 9),
+        ];
+        let mut clusters =
+            find_matching_clusters_from(&grid, seeds, 3, red, &mut ClusterScratch::default())
+                .into_iter()
+                .map(sorted)
+                .collect::<Vec<_>>();
+        clusters.sort();
+
+        assert_eq!(
+            clusters,
+            vec![
+                sorted(vec![
+                    hex::Coord::new(0, 0),
+                    hex::Coord::new(1, 0),
+                    hex::Coord::new(2, 0),
+                ]),
+                sorted(vec![
+                    hex::Coord::new(0, 3),
+                    hex::Coord::new(1, 3),
+                    hex::Coord::new(2, 3),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn mirrored_board_is_symmetric() {
+        for orientation in [hex::Orientation::pointy(), hex::Orientation::flat()] {
+            let settings = GridSettings {
+                width: 9,
+                height: 6,
+                orientation: orientation.clone(),
+                mirrored: true,
+                ..Default::default()
+            };
+            let layout = hex::Layout::new(orientation, Vec2::ONE, Vec2::ZERO);
+            let balls = random_balls(&settings, &layout)
+                .into_iter()
+                .collect::<HashMap<_, _>>();
+
+            for (&hex, species) in &balls {
+                let mirrored = hex::mirror(hex, settings.width, &layout);
+                assert_eq!(balls.get(&mirrored), Some(species), "{:?}", hex);
+            }
+        }
+    }
+
+    #[test]
+    fn new_contacts_after_moving_down() {
+        let (a, b, c, d) = (
+            Entity::from_raw(0),
+            Entity::from_raw(1),
+            Entity::from_raw(2),
+            Entity::from_raw(3),
+        );
+        let before = HashMap::from([
+            (hex::Coord::new(0, 0), a),
+            (hex::Coord::new(1, 1), b),
+            (hex::Coord::new(5, 0), c),
+        ]);
+
+        // even rows move straight down, odd rows down and to the left
+        let mut grid = Grid::default();
+        grid.set(hex::Coord::new(0, 1), Some(a));
+        grid.set(hex::Coord::new(0, 2), Some(b));
+        grid.set(hex::Coord::new(5, 1), Some(c));
+        // spawned in the new row
+        grid.set(hex::Coord::new(3, 0), Some(d));
+
+        assert_eq!(
+            sorted(new_contacts(&before, &grid)),
+            sorted(vec![
+                hex::Coord::new(0, 1),
+                hex::Coord::new(0, 2),
+                hex::Coord::new(3, 0),
+            ])
+        );
+    }
+
+    /// The search [find_cluster] ran before lone balls were short-circuited.
+    fn search_cluster<P>(
+        grid: &Grid,
+        origin: hex::Coord,
+        is_cluster: P,
+    ) -> (Vec<hex::Coord>, HashSet<hex::Coord>)
+    where
+        P: Fn(&Entity) -> bool,
+    {
+        let mut processed = HashSet::from([origin]);
+        let mut to_process = vec![origin];
+        let mut cluster = vec![];
+
+        while let Some(current) = to_process.pop() {
+            if let Some(entity) = grid.get(current) {
+                if !is_cluster(entity) {
+                    continue;
+                }
+                cluster.push(current);
+                for (hex, _) in grid.neighbors(current).iter() {
+                    if processed.insert(*hex) {
+                        to_process.push(*hex);
+                    }
+                }
+            }
+        }
+
+        (cluster, processed)
+    }
+
+    #[test]
+    fn lone_ball_processes_itself_and_its_neighbors() {
+        // (2, 2) is the only red, surrounded by blues on three sides
+        let grid = grid_with(&[(2, 2), (3, 2), (2, 1), (1, 3), (5, 5)]);
+        let is_red = |entity: &Entity| entity.id() == 0;
+        let origin = hex::Coord::new(2, 2);
+
+        let (cluster, processed) = find_cluster(&grid, origin, is_red);
+        assert_eq!(cluster, vec![origin]);
+        assert_eq!(
+            processed,
+            HashSet::from([
+                origin,
+                hex::Coord::new(3, 2),
+                hex::Coord::new(2, 1),
+                hex::Coord::new(1, 3)
+            ])
+        );
+        assert_eq!((cluster, processed), search_cluster(&grid, origin, is_red));
+    }
+
+    #[test]
+    fn find_cluster_matches_a_full_search() {
+        // small LCG, so every run checks the same boards
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 100
+        };
+
+        // shared by every search, like the scratch resource during a game
+        let mut scratch = ClusterScratch::default();
+
+        for _ in 0..50 {
+            let mut hexes = vec![];
+            for r in 0..6 {
+                for q in -3..6 {
+                    if next() < 60 {
+                        hexes.push((q, r));
+                    }
+                }
+            }
+            let grid = grid_with(&hexes);
+            // entity ids index into `hexes`, give each ball one of three colors
+            let colors = hexes.iter().map(|_| next() % 3).collect::<Vec<_>>();
+
+            for &(q, r) in hexes.iter().chain([(-4, 0), (6, 6)].iter()) {
+                let origin = hex::Coord::new(q, r);
+                for color in 0..3 {
+                    let is_cluster = |entity: &Entity| colors[entity.id() as usize] == color;
+                    let (cluster, processed) = find_cluster(&grid, origin, is_cluster);
+                    let (expected_cluster, expected_processed) =
+                        search_cluster(&grid, origin, is_cluster);
+                    assert_eq!(sorted(cluster), sorted(expected_cluster.clone()));
+                    assert_eq!(processed, expected_processed);
+
+                    let cluster = find_cluster_with(&grid, origin, is_cluster, &mut scratch);
+                    assert_eq!(sorted(cluster), sorted(expected_cluster));
+                    assert_eq!(scratch.processed, expected_processed);
+                }
+            }
+        }
     }
 }
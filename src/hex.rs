@@ -1,13 +1,30 @@
 use std::{f32::consts::PI, ops::Add};
 
-use bevy::prelude::*;
+use bevy::{
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use serde::{Deserialize, Serialize};
 
 pub const INNER_RADIUS_COEFF: f32 = 0.866025404;
 
 const SQRT_3: f32 = 1.732_f32;
 
 /// A hex in axial-coordinates.
-#[derive(Component, Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(
+    Component,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct Coord {
     pub q: i32,
     pub r: i32,
@@ -20,10 +37,13 @@ impl Coord {
         Self { q, r }
     }
 
+    /// Returns the adjacent hex in direction `dir`.
     pub fn neighbor(self, dir: Direction) -> Self {
         self + dir.offset()
     }
 
+    /// Returns all six adjacent hexes. Axial offsets are the same for pointy and flat
+    /// orientations, so no [Layout] is needed.
     pub fn neighbors(&self) -> [Self; 6] {
         Direction::all()
             .iter()
@@ -111,7 +131,9 @@ impl Direction {
 }
 
 /// Hexagon orientation coefficients. Often times either [Orientation.pointy] or [Orientation.flat] orientation is used.
-#[derive(Debug, Clone)]
+/// Serialized by name, as `"pointy"` or `"flat"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "OrientationName", from = "OrientationName")]
 pub struct Orientation {
     fwd_matrix: [f32; 4],
     inv_matrix: [f32; 4],
@@ -140,7 +162,32 @@ impl Orientation {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OrientationName {
+    Pointy,
+    Flat,
+}
+
+impl From<Orientation> for OrientationName {
+    fn from(orientation: Orientation) -> Self {
+        match (orientation.angle - POINTY.angle).abs() <= 0.1 {
+            true => OrientationName::Pointy,
+            false => OrientationName::Flat,
+        }
+    }
+}
+
+impl From<OrientationName> for Orientation {
+    fn from(name: OrientationName) -> Self {
+        match name {
+            OrientationName::Pointy => POINTY,
+            OrientationName::Flat => FLAT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layout {
     pub orientation: Orientation,
     pub size: Vec2,
@@ -200,6 +247,38 @@ impl Layout {
         })
     }
 
+    /// Builds a flat outline of a hex, `thickness` wide and centered on the origin. Every hex of
+    /// a layout shares the same shape, move it over a hex with [Layout::to_world_y].
+    pub fn outline_mesh(&self, thickness: f32) -> Mesh {
+        let center = self.to_world(Coord::default());
+        let inset = |corner: Vec2| corner * (1.0 - thickness / corner.length()).max(0.0);
+        let positions = self
+            .hex_corners(Coord::default())
+            .iter()
+            .map(|&corner| corner - center)
+            .flat_map(|corner| [corner, inset(corner)])
+            .map(|point| [point.x, 0.0, point.y])
+            .collect::<Vec<_>>();
+        // a quad between the outer and inner edge on every side
+        let indices = (0..6)
+            .flat_map(|side| {
+                let (outer, inner) = (side * 2, side * 2 + 1);
+                let (next_outer, next_inner) = ((side + 1) % 6 * 2, (side + 1) % 6 * 2 + 1);
+                [outer, inner, next_outer, next_outer, inner, next_inner]
+            })
+            .collect::<Vec<u32>>();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_NORMAL,
+            vec![[0.0, 1.0, 0.0]; positions.len()],
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
     /// Returns the rectangal bounding box of a hex.
     pub fn hex_rect_bounds(&self, hex: Coord) -> Bounds {
         let mut x_min = f32::NAN;
@@ -218,6 +297,12 @@ impl Layout {
         }
     }
 
+    /// Returns the size of a hex along its shorter axis, balls use it so they still fit hexes
+    /// with a non-uniform size.
+    pub fn radius(&self) -> f32 {
+        self.size.min_element()
+    }
+
     /// Returns the hex width and height.
     pub fn hex_size(&self) -> (f32, f32) {
         let (sx, sy) = self.size.into();
@@ -247,6 +332,21 @@ pub fn rectangle(w: i32, h: i32, layout: &Layout) -> impl Iterator<Item = Coord>
     }
 }
 
+/// Reflects `hex` across the center column of a [rectangle] with the given width `w`.
+pub fn mirror(hex: Coord, w: i32, layout: &Layout) -> Coord {
+    match layout.is_pointy() {
+        true => {
+            let column = hex.q + (hex.r >> 1);
+            Coord::new(w - 1 - column - (hex.r >> 1), hex.r)
+        }
+        false => {
+            let row = hex.r + (hex.q >> 1);
+            let q = w - hex.q;
+            Coord::new(q, row - (q >> 1))
+        }
+    }
+}
+
 fn rectangle_pointy(w: i32, h: i32) -> Box<dyn Iterator<Item = Coord>> {
     Box::new((0..=h).flat_map(move |y| (0 - (y >> 1)..w - (y >> 1)).map(move |x| Coord::new(x, y))))
 }
@@ -254,3 +354,92 @@ fn rectangle_pointy(w: i32, h: i32) -> Box<dyn Iterator<Item = Coord>> {
 fn rectangle_flat(w: i32, h: i32) -> Box<dyn Iterator<Item = Coord>> {
     Box::new((0..=w).flat_map(move |x| (0 - (x >> 1)..h - (x >> 1)).map(move |y| Coord::new(x, y))))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubling_size_doubles_world_positions() {
+        let layout = Layout::default();
+        let doubled = Layout::new(Orientation::pointy(), Vec2::splat(2.0), Vec2::ZERO);
+        for hex in rectangle(4, 4, &layout) {
+            let expected = layout.to_world(hex) * 2.0;
+            assert!(doubled.to_world(hex).abs_diff_eq(expected, 1e-4));
+        }
+    }
+
+    fn assert_round_trip(layout: &Layout) {
+        for hex in rectangle(8, 8, layout) {
+            let pos = layout.to_world_y(hex, 0.0);
+            assert_eq!(layout.from_world(pos), hex, "{:?} at {:?}", hex, pos);
+        }
+    }
+
+    #[test]
+    fn round_trip_with_offset_origin() {
+        let origin = Vec2::new(-7.5, 3.25);
+        for orientation in [Orientation::pointy(), Orientation::flat()] {
+            let layout = Layout::new(orientation, Vec2::ONE, origin);
+            assert_round_trip(&layout);
+            assert_eq!(layout.to_world(Coord::default()), origin);
+        }
+    }
+
+    #[test]
+    fn round_trip_with_stretched_size() {
+        for orientation in [Orientation::pointy(), Orientation::flat()] {
+            assert_round_trip(&Layout::new(orientation, Vec2::new(1.5, 1.0), Vec2::ZERO));
+            assert_round_trip(&Layout::new(orientation, Vec2::new(1.0, 1.5), Vec2::ZERO));
+        }
+    }
+
+    #[test]
+    fn orientation_is_serialized_by_name() {
+        let pointy = serde_json::to_string(Orientation::pointy()).unwrap();
+        let flat = serde_json::to_string(Orientation::flat()).unwrap();
+        assert_eq!(pointy, "\"pointy\"");
+        assert_eq!(flat, "\"flat\"");
+
+        let layout = |name: &str| Layout {
+            orientation: serde_json::from_str(name).unwrap(),
+            ..Default::default()
+        };
+        assert!(layout(&pointy).is_pointy());
+        assert!(layout(&flat).is_flat());
+    }
+
+    #[test]
+    fn layout_serde_round_trip() {
+        for orientation in [Orientation::pointy(), Orientation::flat()] {
+            let layout = Layout::new(orientation, Vec2::new(1.5, 1.0), Vec2::new(-2.0, 4.0));
+            let json = serde_json::to_string(&layout).unwrap();
+            let loaded: Layout = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(loaded.is_pointy(), layout.is_pointy());
+            assert_eq!(loaded.size, layout.size);
+            assert_eq!(loaded.origin, layout.origin);
+            for hex in rectangle(4, 4, &layout) {
+                assert_eq!(loaded.to_world(hex), layout.to_world(hex));
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_swaps_cells_across_the_center_column() {
+        for orientation in [Orientation::pointy(), Orientation::flat()] {
+            let layout = Layout::new(orientation, Vec2::ONE, Vec2::ZERO);
+            let cells = rectangle(7, 5, &layout).collect::<Vec<_>>();
+            for &hex in &cells {
+                let mirrored = mirror(hex, 7, &layout);
+                assert!(cells.contains(&mirrored), "{:?} -> {:?}", hex, mirrored);
+                assert_eq!(mirror(mirrored, 7, &layout), hex);
+            }
+        }
+
+        let layout = Layout::default();
+        assert_eq!(mirror(Coord::new(0, 0), 7, &layout), Coord::new(6, 0));
+        assert_eq!(mirror(Coord::new(3, 0), 7, &layout), Coord::new(3, 0));
+        assert_eq!(mirror(Coord::new(-1, 3), 7, &layout), Coord::new(5, 3));
+    }
+}
@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{GameMode, Score, TurnHistory},
+    loading::FontAssets,
+    storage,
+    theme::FontScale,
+    AppState,
+};
+
+const LEADERBOARD_KEY: &str = "ball_shooter_leaderboard";
+
+/// Number of scores kept on the leaderboard.
+pub const LEADERBOARD_SIZE: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub score: u32,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+}
+
+/// Best scores of normal games, highest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Longest run of scoring shots in [GameMode::SuddenDeath].
+    #[serde(default)]
+    pub best_streak: u32,
+}
+
+impl Leaderboard {
+    /// Load the persisted leaderboard, missing or corrupt data yields an empty one.
+    pub fn load() -> Self {
+        let mut leaderboard: Self = storage::load(LEADERBOARD_KEY).unwrap_or_default();
+        leaderboard.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        leaderboard.entries.truncate(LEADERBOARD_SIZE);
+        leaderboard
+    }
+
+    pub fn save(&self) {
+        storage::save(LEADERBOARD_KEY, self);
+    }
+
+    /// Insert `score` if it is among the best [LEADERBOARD_SIZE], returns true if it was.
+    pub fn insert(&mut self, score: u32, timestamp: u64) -> bool {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+
+        if index >= LEADERBOARD_SIZE {
+            return false;
+        }
+
+        self.entries
+            .insert(index, LeaderboardEntry { score, timestamp });
+        self.entries.truncate(LEADERBOARD_SIZE);
+        true
+    }
+
+    /// Record `streak` if it beats [Leaderboard::best_streak], returns true if it did.
+    pub fn record_streak(&mut self, streak: u32) -> bool {
+        if streak <= self.best_streak {
+            return false;
+        }
+        self.best_streak = streak;
+        true
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Formats a unix `timestamp` as a `yyyy-mm-dd` date.
+fn format_date(timestamp: u64) -> String {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = (timestamp / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Spawns the leaderboard as a text node under `parent`.
+pub fn spawn_leaderboard(
+    parent: &mut ChildBuilder,
+    leaderboard: &Leaderboard,
+    font_assets: &FontAssets,
+    font_scale: FontScale,
+) {
+    if leaderboard.entries.is_empty() {
+        return;
+    }
+
+    let style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: font_scale.size(24.0),
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    parent.spawn_bundle(TextBundle {
+        style: Style {
+            margin: UiRect::all(Val::Px(8.0)),
+            ..Default::default()
+        },
+        text: Text {
+            sections: leaderboard
+                .entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| TextSection {
+                    value: format!(
+                        "{}. {}  {}\n",
+                        i + 1,
+                        entry.score,
+                        format_date(entry.timestamp)
+                    ),
+                    style: style.clone(),
+                })
+                .collect(),
+            alignment: Default::default(),
+        },
+        ..Default::default()
+    });
+}
+
+fn load_leaderboard(mut commands: Commands) {
+    commands.insert_resource(Leaderboard::load());
+}
+
+fn record_score(
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    history: Res<TurnHistory>,
+    mut leaderboard: ResMut<Leaderboard>,
+) {
+    // scores from the other modes don't compare, only normal games are ranked
+    let updated = match *mode {
+        GameMode::Normal => leaderboard.insert(score.0, now()),
+        GameMode::SuddenDeath => leaderboard.record_streak(history.longest_streak() as u32),
+        GameMode::Practice | GameMode::Timed => false,
+    };
+    if updated {
+        leaderboard.save();
+    }
+}
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_leaderboard);
+        app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(record_score));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores(leaderboard: &Leaderboard) -> Vec<u32> {
+        leaderboard
+            .entries
+            .iter()
+            .map(|entry| entry.score)
+            .collect()
+    }
+
+    #[test]
+    fn insert_keeps_the_best_scores_in_order() {
+        let mut leaderboard = Leaderboard::default();
+        for score in [30, 10, 50, 20, 40] {
+            assert!(leaderboard.insert(score, 0));
+        }
+        assert_eq!(scores(&leaderboard), vec![50, 40, 30, 20, 10]);
+
+        assert!(leaderboard.insert(35, 0));
+        assert_eq!(scores(&leaderboard), vec![50, 40, 35, 30, 20]);
+
+        // ties rank below the existing score
+        assert!(!leaderboard.insert(20, 0));
+        assert!(!leaderboard.insert(5, 0));
+        assert_eq!(leaderboard.entries.len(), LEADERBOARD_SIZE);
+    }
+
+    #[test]
+    fn only_longer_streaks_are_recorded() {
+        let mut leaderboard = Leaderboard::default();
+        assert!(leaderboard.record_streak(4));
+        assert!(!leaderboard.record_streak(4));
+        assert!(!leaderboard.record_streak(2));
+        assert!(leaderboard.record_streak(7));
+        assert_eq!(leaderboard.best_streak, 7);
+    }
+}
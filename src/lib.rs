@@ -1,23 +1,43 @@
 mod ball;
+mod bindings;
 mod debug;
 mod diagnostics;
 mod game_over;
 mod gameplay;
-mod grid;
-mod hex;
+pub mod grid;
+pub mod hex;
+mod leaderboard;
 mod loading;
+mod localization;
+mod metrics;
+mod music;
+mod navigation;
 mod projectile;
+mod save;
+mod settings;
 mod start_menu;
+mod storage;
+mod theme;
+mod tutorial;
 mod utils;
 
+use crate::bindings::*;
 use crate::debug::*;
 use crate::diagnostics::*;
 use crate::game_over::*;
 use crate::gameplay::*;
 use crate::grid::*;
+use crate::leaderboard::*;
 use crate::loading::*;
+use crate::localization::*;
+use crate::metrics::*;
+use crate::music::*;
 use crate::projectile::*;
+use crate::save::*;
+use crate::settings::*;
 use crate::start_menu::*;
+use crate::theme::*;
+use crate::tutorial::*;
 
 use bevy::prelude::*;
 use bevy::window::PresentMode;
@@ -25,6 +45,7 @@ use bevy::window::WindowMode;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
 use bevy_kira_audio::AudioPlugin;
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub const WINDOW_TITLE: &str = "ball shooter";
 
@@ -36,6 +57,55 @@ enum AppState {
     GameOver,
 }
 
+/// Accessibility setting that disables non-essential animations.
+#[derive(Debug, Clone, Default, Deref, DerefMut)]
+pub struct ReduceMotion(pub bool);
+
+/// MSAA sample count: 1 disables it, 2, 4 and 8 are supported natively. Picked in the settings
+/// menu and applied at runtime. WebGL2 only supports 1 and 4, so on wasm every other count
+/// falls back to 4.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Deref, DerefMut)]
+pub struct MsaaSamples(pub u32);
+
+impl Default for MsaaSamples {
+    fn default() -> Self {
+        Self(4)
+    }
+}
+
+impl MsaaSamples {
+    /// Returns the closest sample count supported on this platform.
+    pub fn supported(self) -> u32 {
+        match self.0 {
+            1 => 1,
+            #[cfg(not(target_arch = "wasm32"))]
+            2 | 8 => self.0,
+            _ => 4,
+        }
+    }
+
+    /// Returns the next supported sample count, wrapping around to 1.
+    pub fn next(self) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        const COUNTS: [u32; 4] = [1, 2, 4, 8];
+        #[cfg(target_arch = "wasm32")]
+        const COUNTS: [u32; 2] = [1, 4];
+
+        let current = self.supported();
+        let index = COUNTS
+            .iter()
+            .position(|&count| count == current)
+            .unwrap_or(0);
+        Self(COUNTS[(index + 1) % COUNTS.len()])
+    }
+}
+
+fn apply_msaa(samples: Res<MsaaSamples>, mut msaa: ResMut<Msaa>) {
+    if samples.is_changed() {
+        msaa.samples = samples.supported();
+    }
+}
+
 pub fn app() -> App {
     let mut app = App::new();
     app.add_plugins_with(DefaultPlugins, |group| {
@@ -47,6 +117,9 @@ pub fn app() -> App {
     #[cfg(debug_assertions)]
     app.add_plugin(DiagnosticsPlugin);
 
+    #[cfg(debug_assertions)]
+    app.add_plugin(MetricsPlugin);
+
     #[cfg(target_arch = "wasm32")]
     {
         app.add_plugin(bevy_web_resizer::Plugin);
@@ -60,8 +133,20 @@ pub fn app() -> App {
     app.add_plugin(GridPlugin);
     app.add_plugin(StartMenuPlugin);
     app.add_plugin(GameOverPlugin);
+    app.add_plugin(SavePlugin);
+    app.add_plugin(ThemePlugin);
+    app.add_plugin(LeaderboardPlugin);
+    app.add_plugin(MusicPlugin);
+    app.add_plugin(LocalizationPlugin);
+    app.add_plugin(TutorialPlugin);
+    app.add_plugin(BindingsPlugin);
+    app.add_plugin(SettingsPlugin);
 
-    app.insert_resource(Msaa { samples: 4 });
+    app.init_resource::<MsaaSamples>();
+    app.insert_resource(Msaa {
+        samples: app.world.resource::<MsaaSamples>().supported(),
+    });
+    app.add_system(apply_msaa);
     app.insert_resource(ClearColor(Color::rgb(0.1, 0.1, 0.1)));
     app.insert_resource(WindowDescriptor {
         title: WINDOW_TITLE.to_string(),
@@ -80,6 +165,7 @@ pub fn app() -> App {
         fit_canvas_to_parent: true,
         ..Default::default()
     });
+    app.init_resource::<ReduceMotion>();
     app.add_state(AppState::Loading);
     app
 }
@@ -17,6 +17,10 @@ pub struct AudioAssets {
     pub soundtrack: Handle<AudioSource>,
     #[asset(path = "audio/score.ogg")]
     pub score: Handle<AudioSource>,
+    /// Played when the grid settles after a descent. Shares the flying clip, which makes a
+    /// passable thud at half speed.
+    #[asset(path = "audio/flying.ogg")]
+    pub settle: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection)]
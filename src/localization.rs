@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::storage;
+
+const LANGUAGE_KEY: &str = "ball_shooter_language";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Swedish,
+}
+
+impl Language {
+    /// Returns every language, in the order they're cycled through.
+    pub fn all() -> &'static [Language] {
+        &[Language::English, Language::Swedish]
+    }
+
+    /// Returns the language after this one.
+    pub fn next(self) -> Self {
+        let all = Self::all();
+        let index = all.iter().position(|language| *language == self).unwrap();
+        all[(index + 1) % all.len()]
+    }
+
+    fn strings(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../assets/locale/en.json"),
+            Language::Swedish => include_str!("../assets/locale/sv.json"),
+        }
+    }
+}
+
+fn parse(language: Language) -> HashMap<String, String> {
+    serde_json::from_str(language.strings()).unwrap_or_else(|err| {
+        warn!("invalid {:?} translations: {}", language, err);
+        HashMap::new()
+    })
+}
+
+/// UI strings of the selected [Language], missing keys fall back to English.
+#[derive(Debug, Clone)]
+pub struct Localization {
+    pub language: Language,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Localization {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            strings: parse(language),
+            fallback: parse(Language::English),
+        }
+    }
+
+    /// Load the saved language, English if none was saved.
+    pub fn load() -> Self {
+        Self::new(storage::load(LANGUAGE_KEY).unwrap_or_default())
+    }
+
+    /// Switch to `language` and remember it.
+    pub fn set_language(&mut self, language: Language) {
+        *self = Self::new(language);
+        storage::save(LANGUAGE_KEY, &language);
+    }
+
+    /// Returns the string at `key`, or the key itself if no translation has it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, |value| value.as_str())
+    }
+
+    /// Returns the string at `key` with each `{}` replaced by the next of `args`.
+    pub fn format(&self, key: &str, args: &[&dyn std::fmt::Display]) -> String {
+        let mut args = args.iter();
+        self.get(key)
+            .split("{}")
+            .enumerate()
+            .fold(String::new(), |mut value, (i, part)| {
+                if i > 0 {
+                    if let Some(arg) = args.next() {
+                        value += &arg.to_string();
+                    }
+                }
+                value + part
+            })
+    }
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::load()
+    }
+}
+
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Localization>();
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    gameplay::{GameMode, Score, TurnHistory},
+    AppState,
+};
+
+/// Key used to log [Metrics].
+pub const METRICS_KEY: KeyCode = KeyCode::F9;
+
+/// Session wide statistics used when balancing, practice games aren't counted.
+#[derive(Serialize, Default, Debug, Clone)]
+pub struct Metrics {
+    pub games: u32,
+    pub shots: u32,
+    /// Number of balls cleared by every shot that cleared any.
+    pub clears: Vec<u32>,
+    /// Final score of every game.
+    pub scores: Vec<u32>,
+}
+
+impl Metrics {
+    pub fn shots_per_game(&self) -> f32 {
+        match self.games {
+            0 => 0.0,
+            games => self.shots as f32 / games as f32,
+        }
+    }
+
+    pub fn average_cleared(&self) -> f32 {
+        match self.clears.len() {
+            0 => 0.0,
+            len => self.clears.iter().sum::<u32>() as f32 / len as f32,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "games": self.games,
+            "shots": self.shots,
+            "shots_per_game": self.shots_per_game(),
+            "average_cleared": self.average_cleared(),
+            "clears": self.clears,
+            "scores": self.scores,
+        })
+        .to_string()
+    }
+}
+
+fn record_metrics(
+    history: Res<TurnHistory>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    mut metrics: ResMut<Metrics>,
+) {
+    if *mode == GameMode::Practice {
+        return;
+    }
+
+    metrics.games += 1;
+    metrics.shots += history.len() as u32;
+    metrics.clears.extend(
+        history
+            .iter()
+            .map(|record| record.cleared)
+            .filter(|&cleared| cleared > 0),
+    );
+    metrics.scores.push(score.0);
+}
+
+fn dump_metrics(keys: Res<Input<KeyCode>>, mut exit: EventReader<AppExit>, metrics: Res<Metrics>) {
+    if keys.just_pressed(METRICS_KEY) || exit.iter().next().is_some() {
+        info!("metrics: {}", metrics.to_json());
+    }
+}
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Metrics>();
+        app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(record_metrics));
+        app.add_system(dump_metrics);
+    }
+}
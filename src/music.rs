@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use bevy_kira_audio::{Audio, AudioSource};
+
+use crate::{loading::AudioAssets, AppState};
+
+/// Volume the soundtrack is played at.
+pub const MUSIC_VOLUME: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Music {
+    Menu,
+    Gameplay,
+}
+
+/// Soundtrack playlists, each one loops back to its first track when the last one finishes.
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    pub menu: Vec<Handle<AudioSource>>,
+    pub gameplay: Vec<Handle<AudioSource>>,
+    current: Option<Music>,
+    track: usize,
+}
+
+impl Playlist {
+    pub fn new(menu: Vec<Handle<AudioSource>>, gameplay: Vec<Handle<AudioSource>>) -> Self {
+        Self {
+            menu,
+            gameplay,
+            current: None,
+            track: 0,
+        }
+    }
+
+    fn tracks(&self) -> &[Handle<AudioSource>] {
+        match self.current {
+            Some(Music::Menu) => &self.menu,
+            Some(Music::Gameplay) => &self.gameplay,
+            None => &[],
+        }
+    }
+
+    /// Returns the track currently due to play.
+    pub fn current_track(&self) -> Option<&Handle<AudioSource>> {
+        self.tracks().get(self.track)
+    }
+
+    /// Advances to the next track, wrapping around.
+    pub fn next_track(&mut self) -> Option<&Handle<AudioSource>> {
+        let len = self.tracks().len();
+        if len == 0 {
+            return None;
+        }
+        self.track = (self.track + 1) % len;
+        self.current_track()
+    }
+}
+
+/// The playing soundtrack instance.
+pub struct SoundtrackAudio {
+    pub instance: Handle<AudioInstance>,
+    pub source: Handle<AudioSource>,
+}
+
+/// Only the one soundtrack ships so far, so both playlists start with it and the music
+/// keeps playing across states. New tracks are added to the lists here.
+fn setup_playlist(mut commands: Commands, audio_assets: Res<AudioAssets>) {
+    commands.insert_resource(Playlist::new(
+        vec![audio_assets.soundtrack.clone()],
+        vec![audio_assets.soundtrack.clone()],
+    ));
+}
+
+fn play(commands: &mut Commands, audio: &Audio, source: &Handle<AudioSource>) {
+    let instance = audio
+        .play(source.clone())
+        .fade_in(AudioTween::linear(Duration::from_secs(5)))
+        .with_volume(MUSIC_VOLUME)
+        .handle();
+
+    commands.insert_resource(SoundtrackAudio {
+        instance,
+        source: source.clone(),
+    });
+}
+
+fn switch_music(
+    music: Music,
+    mut commands: Commands,
+    audio: Res<Audio>,
+    mut playlist: ResMut<Playlist>,
+    soundtrack: Option<Res<SoundtrackAudio>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    if playlist.current == Some(music) {
+        return;
+    }
+
+    playlist.current = Some(music);
+    playlist.track = 0;
+
+    let next = match playlist.current_track() {
+        Some(next) => next.clone(),
+        None => return,
+    };
+
+    if let Some(soundtrack) = soundtrack {
+        // keep playing if the new playlist starts with the same track
+        if soundtrack.source == next {
+            return;
+        }
+        if let Some(instance) = audio_instances.get_mut(&soundtrack.instance) {
+            instance.stop(AudioTween::linear(Duration::from_secs(1)));
+        }
+    }
+
+    play(&mut commands, &audio, &next);
+}
+
+fn play_menu_music(
+    commands: Commands,
+    audio: Res<Audio>,
+    playlist: ResMut<Playlist>,
+    soundtrack: Option<Res<SoundtrackAudio>>,
+    audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    switch_music(
+        Music::Menu,
+        commands,
+        audio,
+        playlist,
+        soundtrack,
+        audio_instances,
+    );
+}
+
+fn play_gameplay_music(
+    commands: Commands,
+    audio: Res<Audio>,
+    playlist: ResMut<Playlist>,
+    soundtrack: Option<Res<SoundtrackAudio>>,
+    audio_instances: ResMut<Assets<AudioInstance>>,
+) {
+    switch_music(
+        Music::Gameplay,
+        commands,
+        audio,
+        playlist,
+        soundtrack,
+        audio_instances,
+    );
+}
+
+fn advance_playlist(
+    mut commands: Commands,
+    audio: Res<Audio>,
+    playlist: Option<ResMut<Playlist>>,
+    soundtrack: Option<Res<SoundtrackAudio>>,
+    audio_instances: Res<Assets<AudioInstance>>,
+) {
+    let (mut playlist, soundtrack) = match (playlist, soundtrack) {
+        (Some(playlist), Some(soundtrack)) => (playlist, soundtrack),
+        _ => return,
+    };
+
+    let finished = match audio_instances.get(&soundtrack.instance) {
+        Some(instance) => matches!(instance.state(), PlaybackState::Stopped),
+        None => false,
+    };
+
+    if !finished {
+        return;
+    }
+
+    if let Some(next) = playlist.next_track().cloned() {
+        play(&mut commands, &audio, &next);
+    }
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_exit(AppState::Loading).with_system(setup_playlist))
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(play_menu_music))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Gameplay).with_system(play_gameplay_music),
+            )
+            .add_system(advance_playlist);
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+
+/// Menu navigation action read from keyboard or gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavInput {
+    Previous,
+    Next,
+    Activate,
+}
+
+/// Index of the focused button in the active menu.
+#[derive(Debug, Clone, Default)]
+pub struct MenuFocus(pub usize);
+
+impl MenuFocus {
+    /// Moves focus according to `input`, wrapping around `len` buttons.
+    pub fn step(&mut self, input: NavInput, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.0 = match input {
+            NavInput::Previous => (self.0 + len - 1) % len,
+            NavInput::Next => (self.0 + 1) % len,
+            NavInput::Activate => self.0.min(len - 1),
+        };
+    }
+}
+
+/// Set before restarting a menu state to rebuild the menu without moving focus, so
+/// changing an option keeps it focused.
+#[derive(Debug, Clone, Default)]
+pub struct KeepMenuFocus(pub bool);
+
+/// Returns the navigation action pressed this frame, if any.
+pub fn nav_input(
+    keys: &Input<KeyCode>,
+    gamepad_buttons: &Input<GamepadButton>,
+) -> Option<NavInput> {
+    let pressed = |key: KeyCode, button: GamepadButtonType| {
+        keys.just_pressed(key)
+            || gamepad_buttons
+                .get_just_pressed()
+                .any(|pressed| pressed.button_type == button)
+    };
+
+    if pressed(KeyCode::Up, GamepadButtonType::DPadUp) {
+        Some(NavInput::Previous)
+    } else if pressed(KeyCode::Down, GamepadButtonType::DPadDown) {
+        Some(NavInput::Next)
+    } else if pressed(KeyCode::Return, GamepadButtonType::South) {
+        Some(NavInput::Activate)
+    } else {
+        None
+    }
+}
+
+/// Resets focus unless [KeepMenuFocus] is set and drops input from the frame a menu is
+/// entered in, so the key that closed the previous menu doesn't activate the next one.
+pub fn reset_menu_navigation(
+    mut focus: ResMut<MenuFocus>,
+    mut keep_focus: ResMut<KeepMenuFocus>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+) {
+    if !keep_focus.0 {
+        focus.0 = 0;
+    }
+    keep_focus.0 = false;
+    keys.clear();
+    gamepad_buttons.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restarting_a_menu_can_keep_focus() {
+        let mut world = World::new();
+        world.insert_resource(MenuFocus(3));
+        world.insert_resource(KeepMenuFocus(true));
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<Input<GamepadButton>>();
+        let mut stage = SystemStage::single_threaded().with_system(reset_menu_navigation);
+
+        stage.run(&mut world);
+        assert_eq!(world.resource::<MenuFocus>().0, 3);
+        assert!(!world.resource::<KeepMenuFocus>().0);
+
+        // entering the menu again starts at the top
+        stage.run(&mut world);
+        assert_eq!(world.resource::<MenuFocus>().0, 0);
+    }
+}
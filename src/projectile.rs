@@ -1,17 +1,27 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::{schedule::ShouldRun, system::SystemParam},
+    prelude::*,
+};
 use bevy_kira_audio::prelude::*;
 use bevy_mod_check_filter::{IsFalse, IsTrue};
 use bevy_prototype_debug_lines::DebugLines;
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    marker::PhantomData,
+};
 
 use crate::{
+    bindings::{Binding, KeyBindings},
     gameplay, hex,
-    loading::{AudioAssets, TextureAssets},
+    loading::AudioAssets,
+    save::ResumeGame,
 };
 
 use super::{
     ball::{self, Species},
-    grid, utils, AppState, MainCamera,
+    grid, utils, AppState, MainCamera, ReduceMotion,
 };
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
@@ -25,6 +35,14 @@ pub struct Projectile;
 #[derive(Component)]
 pub struct Flying(pub bool);
 
+/// Marks the target shown where the aimed projectile is predicted to land.
+#[derive(Component)]
+pub struct Reticle;
+
+/// Point the loaded projectile is aimed at, `None` while no projectile is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct AimTarget(pub Option<Vec3>);
+
 impl std::ops::Deref for Flying {
     type Target = bool;
     fn deref(&self) -> &Self::Target {
@@ -49,8 +67,479 @@ pub struct SpawnedBall {
 #[derive(Clone)]
 pub struct ReloadProjectile;
 
+/// Upcoming projectile species, the front is loaded next.
 #[derive(Clone)]
-pub struct ProjectileBuffer(pub Vec<ball::Species>);
+pub struct ProjectileBuffer {
+    pub queue: VecDeque<ball::Species>,
+    /// Number of upcoming species kept in the queue.
+    pub capacity: usize,
+}
+
+impl ProjectileBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Fills the queue up to capacity with species from `next`.
+    pub fn refill(&mut self, mut next: impl FnMut() -> ball::Species) {
+        while self.queue.len() < self.capacity {
+            self.queue.push_back(next());
+        }
+    }
+
+    /// Drops queued species that aren't in `live` and refills the queue from `next`.
+    pub fn purge(&mut self, live: &HashSet<ball::Species>, next: impl FnMut() -> ball::Species) {
+        self.queue.retain(|species| live.contains(species));
+        self.refill(next);
+    }
+}
+
+impl Default for ProjectileBuffer {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// Species of the next projectile loaded, overriding the buffer and kept colors. Cleared once
+/// it's been loaded, useful for scripted shots and debugging.
+#[derive(Debug, Clone, Default)]
+pub struct ForceNextProjectile(pub Option<ball::Species>);
+
+/// Slows down physics while in [AppState::Gameplay], useful when debugging collisions.
+#[derive(Debug, Clone)]
+pub struct SlowMotion {
+    pub enabled: bool,
+    pub time_scale: f32,
+}
+
+impl Default for SlowMotion {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time_scale: 0.2,
+        }
+    }
+}
+
+/// Freezes physics, the [ProjectileStage], firing and turn resolution while in
+/// [AppState::Gameplay]. UI and other animations keep updating.
+#[derive(Debug, Clone, Default, Deref, DerefMut)]
+pub struct Paused(pub bool);
+
+/// Rapier solver settings, applied at startup. Higher values trade performance for fewer
+/// tunneling projectiles.
+#[derive(Debug, Clone)]
+pub struct PhysicsSettings {
+    /// Physics substeps per frame, defaults to 1.
+    pub substeps: usize,
+    /// Continuous collision detection for the projectile, enabled by default.
+    pub ccd: bool,
+    /// Maximum CCD substeps per physics step, defaults to 1.
+    pub max_ccd_substeps: usize,
+    /// [Restitution] of the projectile against the side walls, 1.0 keeps all speed.
+    pub restitution: f32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            substeps: 1,
+            ccd: true,
+            max_ccd_substeps: 1,
+            restitution: 1.0,
+        }
+    }
+}
+
+/// How the projectile bounces off the side walls.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BounceMode {
+    /// Flip the x velocity.
+    #[default]
+    Flip,
+    /// Mirror the velocity about the wall normal, works for walls at any angle.
+    Reflect,
+}
+
+impl BounceMode {
+    /// Returns the other mode.
+    pub fn next(self) -> Self {
+        match self {
+            BounceMode::Flip => BounceMode::Reflect,
+            BounceMode::Reflect => BounceMode::Flip,
+        }
+    }
+
+    /// Localization key of the mode name.
+    pub fn key(self) -> &'static str {
+        match self {
+            BounceMode::Flip => "bounce_flip",
+            BounceMode::Reflect => "bounce_reflect",
+        }
+    }
+}
+
+/// Mirrors `velocity` about the surface `normal`.
+pub fn reflect(velocity: Vec3, normal: Vec3) -> Vec3 {
+    let normal = normal.normalize_or_zero();
+    velocity - 2.0 * velocity.dot(normal) * normal
+}
+
+/// Returns the velocity after bouncing off a side wall facing `normal`, scaling the part
+/// along the normal by `restitution`.
+pub fn bounce_off_wall(velocity: Vec3, normal: Vec3, mode: BounceMode, restitution: f32) -> Vec3 {
+    let bounced = match mode {
+        BounceMode::Flip => Vec3::new(-velocity.x, velocity.y, velocity.z),
+        BounceMode::Reflect => reflect(velocity, normal),
+    };
+    // the walls are vertical, so only the x velocity loses energy
+    Vec3::new(bounced.x * restitution, bounced.y, bounced.z)
+}
+
+/// Axis the flying projectile spins around.
+#[derive(Debug, Clone, Copy)]
+pub enum SpinAxis {
+    /// Spin around a fixed axis in the projectile's local space.
+    Fixed(Vec3),
+    /// Spin as if rolling along the direction of travel.
+    Rolling,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectileSpin {
+    pub axis: SpinAxis,
+    /// Radians per second, zero disables spinning.
+    pub speed: f32,
+}
+
+impl Default for ProjectileSpin {
+    fn default() -> Self {
+        Self {
+            axis: SpinAxis::Fixed(Vec3::Z),
+            speed: 6.0,
+        }
+    }
+}
+
+/// Whether the aim guide line is drawn.
+#[derive(Debug, Clone, Deref, DerefMut)]
+pub struct AimGuides(pub bool);
+
+impl Default for AimGuides {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Outlines the cell the aimed projectile is predicted to land in while [AimGuides] are shown.
+#[derive(Debug, Clone)]
+pub struct LandingOutline {
+    pub enabled: bool,
+    pub color: Color,
+    /// Width of the outline in world units.
+    pub thickness: f32,
+}
+
+impl Default for LandingOutline {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: Color::rgba(0.9, 0.9, 0.9, 0.6),
+            thickness: 0.1,
+        }
+    }
+}
+
+/// Marks the mesh showing the [LandingOutline].
+#[derive(Component)]
+struct LandingCell;
+
+/// Hides the OS cursor during gameplay, the reticle shows where the shot lands. On wasm this only
+/// hides the cursor over the canvas, it isn't locked to it.
+#[derive(Debug, Clone, Default, Deref, DerefMut)]
+pub struct HideCursor(pub bool);
+
+/// Colors of the aim line, one per segment between wall bounces. The line ends with the last
+/// color.
+#[derive(Debug, Clone)]
+pub struct AimLineColors(pub Vec<Color>);
+
+impl Default for AimLineColors {
+    fn default() -> Self {
+        Self(vec![Color::GREEN, Color::YELLOW, Color::ORANGE])
+    }
+}
+
+/// Only draws the aim line once the aim has been still for `delay` seconds, fading it in
+/// over `fade_in` seconds.
+#[derive(Debug, Clone)]
+pub struct AimLineDelay {
+    pub enabled: bool,
+    pub delay: f32,
+    pub fade_in: f32,
+}
+
+impl Default for AimLineDelay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay: 0.4,
+            fade_in: 0.3,
+        }
+    }
+}
+
+/// How the cursor position is turned into an aim direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AimMode {
+    /// Aim at the point under the cursor.
+    Point,
+    /// Rotate around the shooter, mapping the cursor x-position across the window
+    /// to an angle within `max_angle` radians of straight ahead.
+    Pivot { max_angle: f32 },
+}
+
+impl Default for AimMode {
+    fn default() -> Self {
+        Self::Point
+    }
+}
+
+impl AimMode {
+    /// [AimMode::Pivot] with the limits used when it is picked in the settings menu.
+    pub fn pivot() -> Self {
+        Self::Pivot {
+            max_angle: std::f32::consts::FRAC_PI_3,
+        }
+    }
+}
+
+/// Fading trail drawn behind the flying projectile.
+#[derive(Debug, Clone)]
+pub struct ProjectileTrail {
+    pub enabled: bool,
+    /// Number of recent positions kept.
+    pub length: usize,
+    /// Opacity at the oldest end of the trail, the newest end is opaque.
+    pub fade: f32,
+}
+
+impl Default for ProjectileTrail {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: 16,
+            fade: 0.0,
+        }
+    }
+}
+
+/// Recent positions of a flying projectile, oldest first.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Trail(pub VecDeque<Vec3>);
+
+/// Holding fire charges the projectile, growing it from 1.0 up to `max_scale` over
+/// `charge_time` seconds. The shot is fired on release.
+#[derive(Debug, Clone)]
+pub struct ChargedShot {
+    pub enabled: bool,
+    pub max_scale: f32,
+    pub charge_time: f32,
+}
+
+impl Default for ChargedShot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_scale: 2.0,
+            charge_time: 1.0,
+        }
+    }
+}
+
+/// Remembers a fire input made while the previous shot is resolving and fires the next
+/// projectile as soon as it's loaded. Queued shots aren't charged.
+#[derive(Debug, Clone, Default)]
+pub struct QueuedFire {
+    pub enabled: bool,
+    queued: bool,
+}
+
+impl QueuedFire {
+    /// Queues a fire input made while no projectile is loaded yet.
+    fn queue(&mut self, phase: gameplay::TurnPhase) {
+        if self.enabled
+            && matches!(
+                phase,
+                gameplay::TurnPhase::Resolving | gameplay::TurnPhase::Ready
+            )
+        {
+            self.queued = true;
+        }
+    }
+
+    /// Returns true if a fire input was queued, clearing it.
+    fn take(&mut self) -> bool {
+        std::mem::take(&mut self.queued)
+    }
+}
+
+/// Keeps firing while the fire binding is held, each projectile as soon as it's loaded. Ignored
+/// by charged shots, which fire on release.
+#[derive(Debug, Clone, Default)]
+pub struct AutoFire {
+    pub enabled: bool,
+}
+
+impl AutoFire {
+    /// Returns true if `fire` was pressed, or is held while auto-fire is enabled.
+    fn fires(&self, fire: Binding, keys: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        fire.just_pressed(keys, mouse) || (self.enabled && fire.pressed(keys, mouse))
+    }
+}
+
+fn reset_queued_fire(mut queued_fire: ResMut<QueuedFire>) {
+    queued_fire.queued = false;
+}
+
+/// Holding fire fills a power meter over `fill_time` seconds, the shot is fired on release
+/// with a speed between `min_speed` and `max_speed`.
+#[derive(Debug, Clone)]
+pub struct PowerShot {
+    pub enabled: bool,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub fill_time: f32,
+}
+
+impl PowerShot {
+    /// Returns the speed of a shot fired with `power` in `0.0..=1.0`, kept in a range the
+    /// physics can handle.
+    pub fn speed(&self, power: f32) -> f32 {
+        let speed = self.min_speed + (self.max_speed - self.min_speed) * power.clamp(0.0, 1.0);
+        speed.clamp(MIN_PROJECTILE_SPEED, MAX_PROJECTILE_SPEED)
+    }
+}
+
+impl Default for PowerShot {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_speed: 15.0,
+            max_speed: 45.0,
+            fill_time: 0.6,
+        }
+    }
+}
+
+const PROJECTILE_SPEED: f32 = 30.;
+const MIN_PROJECTILE_SPEED: f32 = 5.;
+/// Faster shots risk skipping past balls, even with CCD.
+const MAX_PROJECTILE_SPEED: f32 = 60.;
+
+/// How far the projectile has been charged, see [ChargedShot].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Charge {
+    /// True while fire is held, only presses made while aiming charge the projectile.
+    pub charging: bool,
+    /// Scale of the projectile, 1.0 is uncharged.
+    pub scale: f32,
+    /// Filled part of the [PowerShot] meter, `0.0..=1.0`.
+    pub power: f32,
+}
+
+impl Default for Charge {
+    fn default() -> Self {
+        Self {
+            charging: false,
+            scale: 1.0,
+            power: 0.0,
+        }
+    }
+}
+
+/// Marks the [PowerShot] meter.
+#[derive(Component)]
+struct PowerMeter;
+
+/// Marks the filled part of the [PowerMeter].
+#[derive(Component)]
+struct PowerMeterFill;
+
+fn setup_power_meter(mut commands: Commands, power_shot: Res<PowerShot>) {
+    if !power_shot.enabled {
+        return;
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(20.0), Val::Px(12.0)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(40.0),
+                    bottom: Val::Px(24.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(PowerMeter)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.9, 0.9, 0.9).into(),
+                    ..default()
+                })
+                .insert(PowerMeterFill);
+        });
+}
+
+fn update_power_meter(
+    projectile: Query<&Charge, (With<Projectile>, IsFalse<Flying>)>,
+    mut fill: Query<&mut Style, With<PowerMeterFill>>,
+) {
+    let power = projectile.get_single().map_or(0.0, |charge| charge.power);
+    for mut style in fill.iter_mut() {
+        style.size.width = Val::Percent(power * 100.0);
+    }
+}
+
+fn cleanup_power_meter(mut commands: Commands, meter: Query<Entity, With<PowerMeter>>) {
+    for entity in meter.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Options changing how the projectile is fired.
+#[derive(SystemParam)]
+struct FireModes<'w, 's> {
+    charged_shot: Res<'w, ChargedShot>,
+    power_shot: Res<'w, PowerShot>,
+    queued_fire: ResMut<'w, QueuedFire>,
+    auto_fire: Res<'w, AutoFire>,
+    paused: Res<'w, Paused>,
+    descent: Res<'w, gameplay::DescentTimer>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// Distance from the shooter to the [AimTarget] in [AimMode::Pivot].
+const PIVOT_AIM_DISTANCE: f32 = 10.0;
+
+/// Returns the point aimed at from `pivot` at `angle` radians off straight up the board.
+fn pivot_point(pivot: Vec3, angle: f32) -> Vec3 {
+    pivot + Vec3::new(angle.sin(), 0.0, -angle.cos()) * PIVOT_AIM_DISTANCE
+}
 
 /// We apply a tiny reduction to the projectile collider radius.
 pub const PROJ_COLLIDER_COEFF: f32 = 0.783;
@@ -62,35 +551,22 @@ pub struct ProjectileBundle {
     pub rigid_body: RigidBody,
     pub ccd: Ccd,
     pub collider: Collider,
+    pub restitution: Restitution,
     pub velocity: Velocity,
     pub collision_events: ActiveEvents,
     pub projectile: Projectile,
     pub is_flying: Flying,
     pub species: Species,
+    pub charge: Charge,
+    pub trail: Trail,
 }
 
 impl ProjectileBundle {
-    pub fn new(
-        pos: Vec3,
-        radius: f32,
-        species: Species,
-        meshes: &mut ResMut<Assets<Mesh>>,
-        materials: &mut ResMut<Assets<StandardMaterial>>,
-        texture_assets: &Res<TextureAssets>,
-    ) -> Self {
+    pub fn new(pos: Vec3, radius: f32, species: Species, assets: &mut ball::BallAssets) -> Self {
         Self {
             pbr: PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Icosphere {
-                    subdivisions: 1,
-                    radius: radius * ball::BALL_RADIUS_COEFF,
-                })),
-                material: materials.add(StandardMaterial {
-                    base_color: ball::species_to_color(species).into(),
-                    base_color_texture: Some(texture_assets.texture_bevy.clone()),
-                    alpha_mode: AlphaMode::Blend,
-                    unlit: true,
-                    ..default()
-                }),
+                mesh: assets.mesh(species, radius),
+                material: assets.material(ball::species_to_color(species)),
                 transform: Transform::from_translation(pos),
                 ..Default::default()
             },
@@ -108,109 +584,608 @@ impl Default for ProjectileBundle {
             pbr: Default::default(),
             rigid_body: RigidBody::KinematicVelocityBased,
             collider: Collider::ball(1.),
+            restitution: Restitution::coefficient(1.0),
             collision_events: ActiveEvents::all(),
             projectile: Projectile,
             is_flying: Flying(false),
             velocity: Velocity::linear(Vec3::new(0., 0., 0.)),
             ccd: Ccd::enabled(),
             species: Species::Red,
+            charge: Charge::default(),
+            trail: Trail::default(),
+        }
+    }
+}
+
+fn restore_projectile_buffer(
+    resume: Res<ResumeGame>,
+    settings: Res<grid::GridSettings>,
+    mut buffer: ResMut<ProjectileBuffer>,
+) {
+    match &resume.0 {
+        Some(saved) => {
+            buffer.queue = saved.buffer.iter().copied().collect();
+            // the saved projectile is popped first when reloading
+            buffer.queue.push_front(saved.projectile);
         }
+        None => buffer.queue = settings.projectiles.iter().copied().collect(),
     }
 }
 
 fn projectile_reload(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut assets: ball::BallAssets,
     mut buffer: ResMut<ProjectileBuffer>,
     begin_turn: EventReader<gameplay::BeginTurn>,
+    mut phase: ResMut<gameplay::TurnPhase>,
+    physics: Res<PhysicsSettings>,
+    settings: Res<gameplay::GameplaySettings>,
+    history: Res<gameplay::TurnHistory>,
     grid: Res<grid::Grid>,
-    texture_assets: Res<TextureAssets>,
+    shooter: Res<gameplay::ShooterPosition>,
+    mut force_next: ResMut<ForceNextProjectile>,
+    fade: Res<gameplay::GameOverFade>,
 ) {
-    if begin_turn.is_empty() {
+    if begin_turn.is_empty() || fade.is_running() {
         return;
     }
 
     begin_turn.clear();
 
-    let species = match buffer.0.pop() {
+    let forced = force_next.0.take();
+
+    let kept = kept_species(&settings, &history);
+
+    let mut species = match forced.or(kept).or_else(|| buffer.queue.pop_front()) {
         Some(species) => species,
-        None => ball::random_species(),
+        None => random_live_species(&grid),
+    };
+
+    buffer.refill(|| random_live_species(&grid));
+
+    // cleared species can linger in the queue, swap in one that's still on the grid
+    if forced.is_none() && settings.usable_color_assist {
+        species = usable_species(species, &buffer, &grid);
+    }
+
+    commands
+        .spawn_bundle(ProjectileBundle::new(
+            shooter.0,
+            grid.layout.radius(),
+            species,
+            &mut assets,
+        ))
+        .insert(Ccd {
+            enabled: physics.ccd,
+        })
+        .insert(Restitution::coefficient(physics.restitution));
+
+    *phase = gameplay::TurnPhase::Aiming;
+}
+
+/// Returns `species`, or a species on the grid if neither it nor the next queued one is.
+fn usable_species(species: Species, buffer: &ProjectileBuffer, grid: &grid::Grid) -> Species {
+    let usable = |species: Option<&Species>| {
+        species.map_or(false, |species| grid.live_species.contains(species))
     };
+    match grid.live_species.is_empty() || usable(Some(&species)) || usable(buffer.queue.front()) {
+        true => species,
+        false => random_live_species(grid),
+    }
+}
+
+/// Returns the species to load again after a shot that scored nothing, see
+/// [gameplay::GameplaySettings::keep_color_until_score].
+fn kept_species(
+    settings: &gameplay::GameplaySettings,
+    history: &gameplay::TurnHistory,
+) -> Option<Species> {
+    match settings.keep_color_until_score {
+        true => history
+            .last()
+            .filter(|record| record.score == 0)
+            .map(|record| record.species),
+        false => None,
+    }
+}
+
+/// Replaces queued species that were cleared from the grid, so they aren't drawn again.
+fn purge_cleared_species(
+    species_cleared: EventReader<gameplay::SpeciesCleared>,
+    mut buffer: ResMut<ProjectileBuffer>,
+    grid: Res<grid::Grid>,
+) {
+    if species_cleared.is_empty() {
+        return;
+    }
+    species_cleared.clear();
+
+    buffer.purge(&grid.live_species, || random_live_species(&grid));
+}
+
+/// Picks a random species among those left on the grid, so cleared colors stop showing up.
+pub fn random_live_species(grid: &grid::Grid) -> Species {
+    let live = Species::all()
+        .iter()
+        .filter(|species| grid.live_species.contains(*species))
+        .collect::<Vec<_>>();
+    match live.is_empty() {
+        true => ball::random_species(),
+        false => *live[rand::random::<usize>() % live.len()],
+    }
+}
+
+fn setup_reticle(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<grid::GridSettings>,
+) {
+    let radius = settings.hex_size.min_element() * ball::BALL_RADIUS_COEFF;
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Torus {
+                radius,
+                ring_radius: radius * 0.1,
+                subdivisions_segments: 24,
+                subdivisions_sides: 8,
+            })),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(0.9, 0.9, 0.9, 0.6),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(Reticle);
+}
 
-    commands.spawn_bundle(ProjectileBundle::new(
-        Vec3::new(0.0, 0.0, gameplay::PLAYER_SPAWN_Z),
-        grid.layout.size.x,
-        species,
-        &mut meshes,
-        &mut materials,
-        &texture_assets,
-    ));
+fn setup_landing_outline(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<LandingOutline>,
+    grid_settings: Res<grid::GridSettings>,
+) {
+    if !settings.enabled {
+        return;
+    }
 
-    buffer.0.push(ball::random_species());
+    // the grid layout may not be set up yet, build the outline from the settings instead
+    let layout = hex::Layout::new(
+        &grid_settings.orientation,
+        grid_settings.hex_size,
+        Vec2::ZERO,
+    );
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(layout.outline_mesh(settings.thickness)),
+            material: materials.add(StandardMaterial {
+                base_color: settings.color,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(LandingCell);
 }
 
 fn aim_projectile(
     windows: Res<Windows>,
     cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut projectile: Query<(Entity, &Transform, &mut Velocity, &mut Flying), IsFalse<Flying>>,
+    mut projectile: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &mut Flying,
+            &mut Charge,
+        ),
+        IsFalse<Flying>,
+    >,
+    mut aim_target: ResMut<AimTarget>,
     mouse: Res<Input<MouseButton>>,
-    mut lines: ResMut<DebugLines>,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    aim_mode: Res<AimMode>,
+    mut modes: FireModes,
+    mut phase: ResMut<gameplay::TurnPhase>,
+    time: Res<Time>,
     audio: Res<bevy_kira_audio::Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
-    if let Ok((_, transform, mut vel, mut is_flying)) = projectile.get_single_mut() {
-        let (camera, camera_transform) = cameras.single();
-        let (ray_pos, ray_dir) = utils::ray_from_mouse_position(
-            windows.get_primary().unwrap(),
-            camera,
-            camera_transform,
-        );
-        let (plane_pos, plane_normal) = (Vec3::new(0., transform.translation.y, 0.), Vec3::Y);
+    if let Ok((_, mut transform, mut vel, mut is_flying, mut charge)) = projectile.get_single_mut()
+    {
+        let point = match (windows.get_primary(), *aim_mode) {
+            // nothing to aim with without a window, shoot straight ahead
+            (None, _) => pivot_point(transform.translation, 0.0),
+            (Some(window), AimMode::Point) => {
+                let (camera, camera_transform) = cameras.single();
+                let (ray_pos, ray_dir) =
+                    utils::ray_from_mouse_position(window, camera, camera_transform);
+                let (plane_pos, plane_normal) =
+                    (Vec3::new(0., transform.translation.y, 0.), Vec3::Y);
+
+                let mut point =
+                    utils::plane_intersection(ray_pos, ray_dir, plane_pos, plane_normal);
+                point.y = transform.translation.y;
+                point.z = point.z.min(transform.translation.z - 5.);
+                point
+            }
+            (Some(window), AimMode::Pivot { max_angle }) => {
+                let cursor_x = window
+                    .cursor_position()
+                    .map_or(0.5, |cursor| cursor.x / window.width());
+                let angle = ((cursor_x * 2.0 - 1.0) * max_angle).clamp(-max_angle, max_angle);
+                pivot_point(transform.translation, angle)
+            }
+        };
+
+        aim_target.0 = Some(point);
 
-        let mut point = utils::plane_intersection(ray_pos, ray_dir, plane_pos, plane_normal);
-        point.y = 0.0;
+        let fire = match modes.charged_shot.enabled || modes.power_shot.enabled {
+            false => modes.auto_fire.fires(bindings.fire, &keys, &mouse),
+            true => {
+                if bindings.fire.just_pressed(&keys, &mouse) {
+                    charge.charging = true;
+                }
 
-        // should use an angle instead
-        point.z = point.z.min(transform.translation.z - 5.);
+                if charge.charging && bindings.fire.pressed(&keys, &mouse) {
+                    let charged_shot = &modes.charged_shot;
+                    if charged_shot.enabled {
+                        let max_scale = charged_shot.max_scale.max(1.0);
+                        let rate = (max_scale - 1.0) / charged_shot.charge_time.max(f32::EPSILON);
+                        charge.scale = (charge.scale + rate * time.delta_seconds()).min(max_scale);
+                        transform.scale = Vec3::splat(charge.scale);
+                    }
 
-        lines.line_colored(transform.translation, point, 0.0, Color::GREEN);
+                    if modes.power_shot.enabled {
+                        let rate = 1.0 / modes.power_shot.fill_time.max(f32::EPSILON);
+                        charge.power = (charge.power + rate * time.delta_seconds()).min(1.0);
+                    }
+                }
 
-        if !mouse.just_pressed(MouseButton::Left) {
+                charge.charging && bindings.fire.just_released(&keys, &mouse)
+            }
+        };
+
+        if *phase != gameplay::TurnPhase::Aiming || modes.paused.0 || modes.descent.is_sliding() {
+            return;
+        }
+
+        let queued = modes.queued_fire.take();
+        if !fire && !queued {
             return;
         }
 
         audio.play(audio_assets.flying.clone());
 
-        const PROJECTILE_SPEED: f32 = 30.;
+        let speed = match modes.power_shot.enabled {
+            true => modes.power_shot.speed(charge.power),
+            false => PROJECTILE_SPEED,
+        };
         let aim_direction = (point - transform.translation).normalize();
-        vel.linvel = aim_direction * PROJECTILE_SPEED;
+        vel.linvel = aim_direction * speed;
 
         is_flying.0 = true;
+        *phase = gameplay::TurnPhase::Flying;
+    } else {
+        aim_target.0 = None;
+
+        if bindings.fire.just_pressed(&keys, &mouse) {
+            modes.queued_fire.queue(*phase);
+        }
     }
 }
 
-fn bounce_on_world_bounds(
-    mut projectile: Query<(Entity, &mut Transform, &mut Velocity, &Collider), IsTrue<Flying>>,
-    mut snap_projectile: EventWriter<SnapProjectile>,
-    grid: Res<grid::Grid>,
-) {
-    if let Ok((_, mut transform, mut vel, collider)) = projectile.get_single_mut() {
-        if let Some(shape) = collider.raw.as_ball() {
-            const SKIN_WIDTH: f32 = 0.1;
-            let skin = shape.radius + SKIN_WIDTH;
+/// Predicts the path of a projectile with `radius` fired from `start` towards `direction`,
+/// bouncing off the side walls. Returns the start, every bounce and the point where it reaches
+/// the top or a ball, or its last bounce once `max_bounces` is exceeded.
+pub fn predict_path(
+    grid: &grid::Grid,
+    start: Vec3,
+    direction: Vec3,
+    radius: f32,
+    max_bounces: usize,
+) -> Vec<Vec3> {
+    const MAX_STEPS: usize = 4096;
 
-            let (clamped, was_clamped_x, was_clamped_y) =
-                clamp_inside_world_bounds(transform.translation, skin, &grid.bounds);
+    let mut direction = Vec3::new(direction.x, 0.0, direction.z).normalize_or_zero();
+    let mut pos = start;
+    let mut path = vec![start];
+    if direction == Vec3::ZERO {
+        return path;
+    }
 
-            transform.translation = clamped;
+    let step = (radius * 0.5).max(0.05);
+    let ball_radius = grid.layout.radius() * ball::BALL_RADIUS_COEFF;
+    let bounds = &grid.bounds;
+    for _ in 0..MAX_STEPS {
+        pos += direction * step;
 
-            if was_clamped_x {
-                vel.linvel.x = -vel.linvel.x;
+        let hex = grid.layout.from_world(pos);
+        let hit = std::iter::once(hex)
+            .chain(hex.neighbors())
+            .filter(|&hex| grid.get(hex).is_some())
+            .any(|hex| grid.layout.to_world_y(hex, pos.y).distance(pos) < radius + ball_radius);
+        if hit || pos.z - radius <= bounds.mins.y {
+            break;
+        }
+
+        let left = pos.x - radius <= bounds.mins.x && direction.x < 0.0;
+        let right = pos.x + radius >= bounds.maxs.x && direction.x > 0.0;
+        if left || right {
+            path.push(pos);
+            if path.len() > max_bounces + 1 {
+                return path;
             }
+            direction.x = -direction.x;
+        }
+    }
 
-            // We hit the top, snap ball
-            if was_clamped_y {
+    path.push(pos);
+    path
+}
+
+/// Predicts the path of the loaded projectile toward the [AimTarget], see [predict_path].
+fn aimed_path(
+    grid: &grid::Grid,
+    projectile: &Query<&Transform, (With<Projectile>, IsFalse<Flying>)>,
+    aim_target: &AimTarget,
+    max_bounces: usize,
+) -> Option<Vec<Vec3>> {
+    let (transform, target) = match (projectile.get_single(), aim_target.0) {
+        (Ok(transform), Some(target)) => (transform, target),
+        _ => return None,
+    };
+
+    let radius =
+        grid.layout.radius() * ball::BALL_RADIUS_COEFF * PROJ_COLLIDER_COEFF * transform.scale.x;
+    Some(predict_path(
+        grid,
+        transform.translation,
+        target - transform.translation,
+        radius,
+        max_bounces,
+    ))
+}
+
+/// Places the [Reticle] where the aimed projectile is predicted to land.
+fn update_reticle(
+    colors: Res<AimLineColors>,
+    grid: Res<grid::Grid>,
+    projectile: Query<&Transform, (With<Projectile>, IsFalse<Flying>)>,
+    aim_target: Res<AimTarget>,
+    mut reticle: Query<(&mut Transform, &mut Visibility), (With<Reticle>, Without<Projectile>)>,
+) {
+    let (mut transform, mut visibility) = match reticle.get_single_mut() {
+        Ok(reticle) => reticle,
+        Err(_) => return,
+    };
+
+    let max_bounces = colors.0.len().saturating_sub(1);
+    let landing = aimed_path(&grid, &projectile, &aim_target, max_bounces)
+        .and_then(|path| path.last().copied());
+    visibility.is_visible = landing.is_some();
+    if let Some(landing) = landing {
+        transform.translation = landing;
+    }
+}
+
+/// Draws the predicted path of the loaded projectile, colored by the number of bounces.
+fn draw_aim_line(
+    aim_guides: Res<AimGuides>,
+    colors: Res<AimLineColors>,
+    grid: Res<grid::Grid>,
+    projectile: Query<&Transform, (With<Projectile>, IsFalse<Flying>)>,
+    aim_target: Res<AimTarget>,
+    aim_delay: Res<AimLineDelay>,
+    time: Res<Time>,
+    mut idle: Local<(Vec3, f32)>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !aim_guides.0 || colors.0.is_empty() {
+        return;
+    }
+
+    let alpha = match aim_delay.enabled {
+        true => {
+            let target = match aim_target.0 {
+                Some(target) => target,
+                None => return,
+            };
+            // any movement of the aim restarts the delay
+            let (last_target, idle_time) = &mut *idle;
+            if last_target.distance_squared(target) > 1e-4 {
+                *last_target = target;
+                *idle_time = 0.0;
+            } else {
+                *idle_time += time.delta_seconds();
+            }
+            ((*idle_time - aim_delay.delay) / aim_delay.fade_in.max(f32::EPSILON)).clamp(0.0, 1.0)
+        }
+        false => 1.0,
+    };
+    if alpha <= 0.0 {
+        return;
+    }
+
+    let path = match aimed_path(&grid, &projectile, &aim_target, colors.0.len() - 1) {
+        Some(path) => path,
+        None => return,
+    };
+    for (segment, &color) in path.windows(2).zip(colors.0.iter()) {
+        let mut color = color;
+        color.set_a(color.a() * alpha);
+        lines.line_colored(segment[0], segment[1], 0.0, color);
+    }
+}
+
+/// Moves the [LandingOutline] over the cell at the end of the predicted path.
+fn update_landing_outline(
+    aim_guides: Res<AimGuides>,
+    colors: Res<AimLineColors>,
+    grid: Res<grid::Grid>,
+    projectile: Query<&Transform, (With<Projectile>, IsFalse<Flying>)>,
+    aim_target: Res<AimTarget>,
+    mut outline: Query<(&mut Transform, &mut Visibility), (With<LandingCell>, Without<Projectile>)>,
+) {
+    let (mut transform, mut visibility) = match outline.get_single_mut() {
+        Ok(outline) => outline,
+        Err(_) => return,
+    };
+
+    let max_bounces = colors.0.len().saturating_sub(1);
+    let landing = match aim_guides.0 {
+        true => aimed_path(&grid, &projectile, &aim_target, max_bounces)
+            .and_then(|path| path.last().copied()),
+        false => None,
+    };
+    visibility.is_visible = landing.is_some();
+    if let Some(landing) = landing {
+        let hex = grid.layout.from_world(landing);
+        transform.translation = grid.layout.to_world_y(hex, landing.y);
+    }
+}
+
+/// Draws the limits of [AimMode::Pivot] as an arc around the shooter.
+fn draw_aim_arc(
+    aim_guides: Res<AimGuides>,
+    aim_mode: Res<AimMode>,
+    shooter: Res<gameplay::ShooterPosition>,
+    mut lines: ResMut<DebugLines>,
+) {
+    let max_angle = match *aim_mode {
+        AimMode::Pivot { max_angle } if aim_guides.0 => max_angle,
+        _ => return,
+    };
+
+    const SEGMENTS: usize = 16;
+    let color = Color::rgba(1.0, 1.0, 1.0, 0.25);
+    let pivot = shooter.0;
+    let at = |angle: f32| pivot_point(pivot, angle);
+
+    lines.line_colored(pivot, at(-max_angle), 0.0, color);
+    lines.line_colored(pivot, at(max_angle), 0.0, color);
+    for i in 0..SEGMENTS {
+        let angle = |i: usize| -max_angle + 2.0 * max_angle * i as f32 / SEGMENTS as f32;
+        lines.line_colored(at(angle(i)), at(angle(i + 1)), 0.0, color);
+    }
+}
+
+fn toggle_aim_guides(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut aim_guides: ResMut<AimGuides>,
+) {
+    if keys.just_pressed(bindings.toggle_aim_guides) {
+        aim_guides.0 = !aim_guides.0;
+    }
+}
+
+#[cfg(debug_assertions)]
+fn display_projectile_velocity(
+    projectile: Query<(&Transform, &Velocity), (With<Projectile>, IsTrue<Flying>)>,
+    mut lines: ResMut<DebugLines>,
+) {
+    for (transform, vel) in projectile.iter() {
+        lines.line_colored(
+            transform.translation,
+            transform.translation + vel.linvel * 0.1,
+            0.0,
+            Color::YELLOW,
+        );
+    }
+}
+
+/// Circles the balls that would drop if the ball under the cursor were cleared.
+#[cfg(debug_assertions)]
+fn display_hanging_balls(
+    grid: Res<grid::Grid>,
+    aim_target: Res<AimTarget>,
+    mut lines: ResMut<DebugLines>,
+) {
+    use crate::debug::DebugLinesExt;
+
+    let target = match aim_target.0 {
+        Some(target) => target,
+        None => return,
+    };
+
+    let hex = grid.layout.from_world(target);
+    if grid.get(hex).is_none() {
+        return;
+    }
+
+    let (radius, _) = grid.layout.hex_size();
+    for hex in grid::floating_if_removed(&grid, &[hex]) {
+        lines.circle(
+            grid.layout.to_world_y(hex, 0.0),
+            Quat::IDENTITY,
+            radius * ball::BALL_RADIUS_COEFF,
+            0.0,
+            Color::RED,
+        );
+    }
+}
+
+/// The projectile is kinematic, so wall bounces and their [Restitution] are applied here
+/// rather than by Rapier.
+fn bounce_on_world_bounds(
+    mut projectile: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &Collider,
+            &Restitution,
+        ),
+        IsTrue<Flying>,
+    >,
+    mut snap_projectile: EventWriter<SnapProjectile>,
+    grid: Res<grid::Grid>,
+    bounce_mode: Res<BounceMode>,
+    aim_guides: Res<AimGuides>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if let Ok((_, mut transform, mut vel, collider, restitution)) = projectile.get_single_mut() {
+        if let Some(shape) = collider.raw.as_ball() {
+            const SKIN_WIDTH: f32 = 0.1;
+            // charged projectiles are scaled up, the collider radius isn't
+            let skin = shape.radius * transform.scale.x + SKIN_WIDTH;
+
+            let (clamped, was_clamped_x, was_clamped_y) =
+                clamp_inside_world_bounds(transform.translation, skin, &grid.bounds);
+
+            transform.translation = clamped;
+
+            if was_clamped_x {
+                let center = (grid.bounds.mins.x + grid.bounds.maxs.x) / 2.0;
+                let normal = match clamped.x < center {
+                    true => Vec3::X,
+                    false => -Vec3::X,
+                };
+                vel.linvel =
+                    bounce_off_wall(vel.linvel, normal, *bounce_mode, restitution.coefficient);
+
+                if **aim_guides {
+                    lines.line_colored(
+                        transform.translation,
+                        transform.translation + vel.linvel * 0.1,
+                        0.5,
+                        Color::ORANGE,
+                    );
+                }
+            }
+
+            // We hit the top, snap ball
+            if was_clamped_y {
                 vel.linvel = Vec3::ZERO;
                 snap_projectile.send(SnapProjectile {
                     entity: None,
@@ -256,54 +1231,318 @@ fn on_projectile_collisions_events(
     mut projectile: Query<(Entity, &mut Velocity, &Transform), (With<Projectile>, IsTrue<Flying>)>,
     balls: Query<(Entity, &Transform), With<ball::Ball>>,
 ) {
-    for (d1, d2, _) in collision_events.iter().filter_map(|e| match e {
-        CollisionEvent::Started(a, b, f) => Some((a, b, f)),
-        CollisionEvent::Stopped(_, _, _) => None,
-    }) {
-        let mut p1 = projectile.get_mut(*d1);
-        if p1.is_err() {
-            p1 = projectile.get_mut(*d2);
+    let started = collision_events
+        .iter()
+        .filter_map(|e| match e {
+            CollisionEvent::Started(a, b, _) => Some((*a, *b)),
+            CollisionEvent::Stopped(_, _, _) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let (projectile, mut vel, tr) = match projectile.get_single_mut() {
+        Ok(projectile) => projectile,
+        Err(_) => return,
+    };
+
+    // several contacts can start in the same frame, snap against the closest ball rather
+    // than whichever was reported first
+    let closest = started
+        .iter()
+        .filter_map(|&(d1, d2)| match (d1 == projectile, d2 == projectile) {
+            (true, _) => balls.get(d2).ok(),
+            (_, true) => balls.get(d1).ok(),
+            _ => None,
+        })
+        .min_by(|(_, a), (_, b)| {
+            let a = a.translation.distance_squared(tr.translation);
+            let b = b.translation.distance_squared(tr.translation);
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+    if let Some((entity, otr)) = closest {
+        let hit_normal = (otr.translation - tr.translation).normalize();
+        vel.linvel = Vec3::ZERO;
+        snap_projectile.send(SnapProjectile {
+            entity: Some(entity),
+            hit_normal: Some(hit_normal),
+        });
+    }
+}
+
+/// Logs an error when more than one projectile is loaded, most systems expect a single one.
+#[cfg(debug_assertions)]
+fn check_single_projectile(
+    projectiles: Query<Entity, (With<Projectile>, IsFalse<Flying>)>,
+    mut reported: Local<bool>,
+) {
+    let loaded = projectiles.iter().collect::<Vec<_>>();
+    // only report once until the extra projectiles are gone
+    if loaded.len() > 1 && !*reported {
+        error!(
+            "{} projectiles loaded at once, expected at most one: {:?}",
+            loaded.len(),
+            loaded
+        );
+    }
+    *reported = loaded.len() > 1;
+}
+
+/// Cycles the species of the loaded projectile with the mouse wheel.
+#[cfg(debug_assertions)]
+fn cycle_projectile_species(
+    mut wheel: EventReader<bevy::input::mouse::MouseWheel>,
+    mut projectile: Query<
+        (
+            &mut Species,
+            &mut Handle<StandardMaterial>,
+            &mut Handle<Mesh>,
+        ),
+        (With<Projectile>, IsFalse<Flying>),
+    >,
+    grid: Res<grid::Grid>,
+    mut assets: ball::BallAssets,
+) {
+    let steps = wheel
+        .iter()
+        .map(|event| event.y.signum() as i32)
+        .sum::<i32>();
+    if steps == 0 {
+        return;
+    }
+
+    if let Ok((mut species, mut material, mut mesh)) = projectile.get_single_mut() {
+        let all = Species::all();
+        let index = all.iter().position(|s| *s == *species).unwrap() as i32;
+        *species = all[(index + steps).rem_euclid(Species::COUNT as i32) as usize];
+
+        *material = assets.material(ball::species_to_color(*species));
+        *mesh = assets.mesh(*species, grid.layout.radius());
+    }
+}
+
+fn rotate_projectile(
+    mut query: Query<(&mut Transform, &Velocity), (With<Projectile>, IsTrue<Flying>)>,
+    spin: Res<ProjectileSpin>,
+    reduce_motion: Res<ReduceMotion>,
+    time: Res<Time>,
+) {
+    if spin.speed == 0.0 || reduce_motion.0 {
+        return;
+    }
+
+    let angle = spin.speed * time.delta_seconds();
+    for (mut transform, vel) in query.iter_mut() {
+        match spin.axis {
+            SpinAxis::Fixed(axis) => {
+                let axis = axis.normalize_or_zero();
+                if axis != Vec3::ZERO {
+                    transform.rotation *= Quat::from_axis_angle(axis, angle);
+                }
+            }
+            SpinAxis::Rolling => {
+                let axis = Vec3::Y.cross(vel.linvel).normalize_or_zero();
+                if axis != Vec3::ZERO {
+                    transform.rotation = Quat::from_axis_angle(axis, angle) * transform.rotation;
+                }
+            }
+        }
+    }
+}
+
+fn draw_projectile_trail(
+    mut query: Query<(&Transform, &Species, &mut Trail), (With<Projectile>, IsTrue<Flying>)>,
+    settings: Res<ProjectileTrail>,
+    reduce_motion: Res<ReduceMotion>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !settings.enabled || reduce_motion.0 {
+        return;
+    }
+
+    for (transform, species, mut trail) in query.iter_mut() {
+        trail.0.push_back(transform.translation);
+        while trail.0.len() > settings.length.max(2) {
+            trail.0.pop_front();
         }
 
-        if let Ok((entity, otr)) = balls.get(*d1).or(balls.get(*d2)) {
-            let (_, mut vel, tr) = p1.unwrap();
-            let hit_normal = (otr.translation - tr.translation).normalize();
-            vel.linvel = Vec3::ZERO;
-            snap_projectile.send(SnapProjectile {
-                entity: Some(entity),
-                hit_normal: Some(hit_normal),
-            });
+        let color = ball::species_to_color(*species);
+        let segments = trail.0.len().saturating_sub(1);
+        let alpha = |i: usize| settings.fade + (1.0 - settings.fade) * i as f32 / segments as f32;
+        for (i, (start, end)) in trail.0.iter().zip(trail.0.iter().skip(1)).enumerate() {
+            lines.line_gradient(
+                *start,
+                *end,
+                0.0,
+                *color.clone().set_a(alpha(i)),
+                *color.clone().set_a(alpha(i + 1)),
+            );
         }
     }
 }
 
-fn rotate_projectile(
-    mut query: Query<(Entity, &mut Transform), (With<Projectile>, IsTrue<Flying>)>,
+fn toggle_slow_motion(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut slow_motion: ResMut<SlowMotion>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !keys.just_pressed(bindings.toggle_slow_motion) {
+        return;
+    }
+
+    slow_motion.enabled = !slow_motion.enabled;
+    let scale = match slow_motion.enabled {
+        true => slow_motion.time_scale,
+        false => 1.0,
+    };
+    set_physics_time_scale(&mut rapier_config, scale);
+}
+
+fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut paused: ResMut<Paused>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !keys.just_pressed(bindings.toggle_pause) {
+        return;
+    }
+
+    paused.0 = !paused.0;
+    rapier_config.physics_pipeline_active = !paused.0;
+}
+
+fn reset_pause(mut paused: ResMut<Paused>, mut rapier_config: ResMut<RapierConfiguration>) {
+    paused.0 = false;
+    rapier_config.physics_pipeline_active = true;
+}
+
+/// Skips the [ProjectileStage] while [Paused].
+fn run_if_not_paused(paused: Res<Paused>) -> ShouldRun {
+    match paused.0 {
+        true => ShouldRun::No,
+        false => ShouldRun::Yes,
+    }
+}
+
+fn reset_slow_motion(
+    mut slow_motion: ResMut<SlowMotion>,
+    mut rapier_config: ResMut<RapierConfiguration>,
 ) {
-    for (_, mut transform) in query.iter_mut() {
-        transform.rotation *= Quat::from_rotation_z(0.1);
+    slow_motion.enabled = false;
+    set_physics_time_scale(&mut rapier_config, 1.0);
+}
+
+fn apply_physics_settings(
+    settings: Res<PhysicsSettings>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut rapier_context: ResMut<RapierContext>,
+) {
+    if let TimestepMode::Variable { substeps, .. } = &mut rapier_config.timestep_mode {
+        *substeps = settings.substeps.max(1);
     }
+    rapier_context.integration_parameters.max_ccd_substeps = settings.max_ccd_substeps.max(1);
 }
 
-fn cleanup_projectile(mut commands: Commands, projectile: Query<Entity, With<Projectile>>) {
-    if !projectile.iter().next().is_none() {
-        commands.entity(projectile.single()).despawn_recursive();
+fn set_physics_time_scale(rapier_config: &mut RapierConfiguration, scale: f32) {
+    if let TimestepMode::Variable { time_scale, .. } = &mut rapier_config.timestep_mode {
+        *time_scale = scale;
     }
 }
 
+fn cleanup_projectile(
+    mut commands: Commands,
+    projectile: Query<Entity, With<Projectile>>,
+    mut buffer: ResMut<ProjectileBuffer>,
+) {
+    for entity in projectile.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    buffer.queue.clear();
+}
+
+fn hide_cursor(hide_cursor: Res<HideCursor>, mut windows: ResMut<Windows>) {
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_cursor_visibility(!hide_cursor.0);
+    }
+}
+
+fn show_cursor(mut windows: ResMut<Windows>) {
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_cursor_visibility(true);
+    }
+}
+
+fn cleanup_reticle(
+    mut commands: Commands,
+    reticle: Query<Entity, Or<(With<Reticle>, With<LandingCell>)>>,
+    mut aim_target: ResMut<AimTarget>,
+) {
+    for entity in reticle.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    aim_target.0 = None;
+}
+
 pub struct ProjectilePlugin;
 
 impl Plugin for ProjectilePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SnapProjectile>();
         app.add_event::<SpawnedBall>();
-        app.insert_resource(ProjectileBuffer(vec![ball::random_species()]));
+        app.init_resource::<ProjectileBuffer>();
+        app.init_resource::<ForceNextProjectile>();
+        app.init_resource::<SlowMotion>();
+        app.init_resource::<Paused>();
+        app.init_resource::<ProjectileSpin>();
+        app.init_resource::<AimGuides>();
+        app.init_resource::<HideCursor>();
+        app.init_resource::<AimMode>();
+        app.init_resource::<AimTarget>();
+        app.init_resource::<AimLineColors>();
+        app.init_resource::<AimLineDelay>();
+        app.init_resource::<LandingOutline>();
+        app.init_resource::<ChargedShot>();
+        app.init_resource::<PowerShot>();
+        app.init_resource::<QueuedFire>();
+        app.init_resource::<AutoFire>();
+        app.init_resource::<ProjectileTrail>();
+        app.init_resource::<PhysicsSettings>();
+        app.init_resource::<BounceMode>();
+        app.add_startup_system(apply_physics_settings);
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Gameplay)
+                .with_system(setup_reticle)
+                .with_system(setup_landing_outline)
+                .with_system(setup_power_meter)
+                .with_system(hide_cursor)
+                .with_system(reset_queued_fire)
+                .with_system(restore_projectile_buffer),
+        );
         app.add_system_set(
             SystemSet::on_update(AppState::Gameplay)
+                .with_system(toggle_slow_motion)
+                .with_system(toggle_pause)
                 .with_system(rotate_projectile)
+                .with_system(draw_projectile_trail)
+                .with_system(purge_cleared_species.before(projectile_reload))
                 .with_system(projectile_reload)
+                .with_system(toggle_aim_guides)
+                .with_system(draw_aim_arc)
+                .with_system(update_reticle.after(aim_projectile))
+                .with_system(draw_aim_line.after(aim_projectile))
+                .with_system(update_landing_outline.after(aim_projectile))
+                .with_system(update_power_meter.after(aim_projectile))
                 .with_system(aim_projectile),
         );
+        #[cfg(debug_assertions)]
+        app.add_system_set(
+            SystemSet::on_update(AppState::Gameplay)
+                .with_system(display_projectile_velocity)
+                .with_system(display_hanging_balls)
+                .with_system(cycle_projectile_species)
+                .with_system(check_single_projectile),
+        );
         app.add_stage_before(
             PhysicsStages::SyncBackend,
             ProjectileStage::Update,
@@ -312,9 +1551,731 @@ impl Plugin for ProjectilePlugin {
         app.add_system_set_to_stage(
             ProjectileStage::Update,
             SystemSet::new()
+                .with_run_criteria(run_if_not_paused)
                 .with_system(bounce_on_world_bounds)
                 .with_system(on_projectile_collisions_events),
         );
-        app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(cleanup_projectile));
+        app.add_system_set(
+            SystemSet::on_exit(AppState::Gameplay)
+                .with_system(cleanup_projectile)
+                .with_system(cleanup_reticle)
+                .with_system(show_cursor)
+                .with_system(reset_slow_motion)
+                .with_system(reset_pause)
+                .with_system(cleanup_power_meter),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_stays_at_capacity() {
+        let mut buffer = ProjectileBuffer::new(3);
+        let mut next = Species::all().iter().copied().cycle();
+        buffer.refill(|| next.next().unwrap());
+        assert_eq!(buffer.queue, [Species::Red, Species::Blue, Species::Green]);
+
+        for turn in 0..5 {
+            let loaded = buffer.queue.pop_front().unwrap();
+            assert_eq!(loaded, Species::all()[turn % Species::COUNT]);
+            buffer.refill(|| next.next().unwrap());
+            assert_eq!(buffer.queue.len(), 3);
+        }
+    }
+
+    #[test]
+    fn cleared_species_are_purged_from_the_buffer() {
+        let mut grid = grid::Grid::default();
+        grid.live_species = HashSet::from([Species::Blue, Species::Green]);
+
+        let mut buffer = ProjectileBuffer::new(3);
+        buffer.queue = VecDeque::from([Species::Red, Species::Blue, Species::Red]);
+        buffer.purge(&grid.live_species, || random_live_species(&grid));
+
+        assert_eq!(buffer.queue.len(), 3);
+        assert_eq!(buffer.queue[0], Species::Blue);
+        assert!(buffer.queue.iter().all(|s| grid.live_species.contains(s)));
+    }
+
+    #[test]
+    fn cleared_species_are_not_drawn() {
+        let mut grid = grid::Grid::default();
+        grid.live_species = HashSet::from([Species::Blue, Species::Green]);
+        for _ in 0..100 {
+            assert_ne!(random_live_species(&grid), Species::Red);
+        }
+    }
+
+    #[test]
+    fn physics_settings_are_applied_to_rapier() {
+        let mut world = World::new();
+        world.insert_resource(PhysicsSettings {
+            substeps: 4,
+            max_ccd_substeps: 3,
+            ..Default::default()
+        });
+        world.insert_resource(RapierConfiguration::default());
+        world.insert_resource(RapierContext::default());
+
+        SystemStage::single_threaded()
+            .with_system(apply_physics_settings)
+            .run(&mut world);
+
+        match world.resource::<RapierConfiguration>().timestep_mode {
+            TimestepMode::Variable { substeps, .. } => assert_eq!(substeps, 4),
+            _ => panic!("expected a variable timestep"),
+        }
+        let context = world.resource::<RapierContext>();
+        assert_eq!(context.integration_parameters.max_ccd_substeps, 3);
+    }
+
+    /// Steps Rapier the way the plugin does with `settings` applied, firing a projectile at
+    /// [MAX_PROJECTILE_SPEED] towards a thin collider. Returns true if they touched.
+    fn hits_thin_collider(settings: PhysicsSettings) -> bool {
+        use bevy_rapier3d::rapier::prelude as rapier;
+
+        let mut world = World::new();
+        world.insert_resource(settings.clone());
+        world.insert_resource(RapierConfiguration::default());
+        world.insert_resource(RapierContext::default());
+        SystemStage::single_threaded()
+            .with_system(apply_physics_settings)
+            .run(&mut world);
+
+        let substeps = match world.resource::<RapierConfiguration>().timestep_mode {
+            TimestepMode::Variable { substeps, .. } => substeps,
+            _ => panic!("expected a variable timestep"),
+        };
+        let mut context = world.remove_resource::<RapierContext>().unwrap();
+
+        let collision_types =
+            rapier::ActiveCollisionTypes::default() | rapier::ActiveCollisionTypes::KINEMATIC_FIXED;
+        let body = context.bodies.insert(
+            rapier::RigidBodyBuilder::kinematic_velocity_based()
+                .linvel(rapier::Vector::new(0.0, 0.0, -MAX_PROJECTILE_SPEED))
+                .ccd_enabled(settings.ccd)
+                .build(),
+        );
+        let projectile = context.colliders.insert_with_parent(
+            rapier::ColliderBuilder::ball(0.1)
+                .active_collision_types(collision_types)
+                .build(),
+            body,
+            &mut context.bodies,
+        );
+        // one frame at full speed moves the projectile a whole unit, well past the wall
+        let wall = context.colliders.insert(
+            rapier::ColliderBuilder::cuboid(5.0, 5.0, 0.05)
+                .translation(rapier::Vector::new(0.0, 0.0, -5.5))
+                .active_collision_types(collision_types)
+                .build(),
+        );
+
+        let frame = 1.0 / 60.0;
+        context.integration_parameters.dt = frame / substeps as f32;
+        for _ in 0..10 * substeps {
+            context.pipeline.step(
+                &rapier::Vector::zeros(),
+                &context.integration_parameters,
+                &mut context.islands,
+                &mut context.broad_phase,
+                &mut context.narrow_phase,
+                &mut context.bodies,
+                &mut context.colliders,
+                &mut context.impulse_joints,
+                &mut context.multibody_joints,
+                &mut context.ccd_solver,
+                &(),
+                &(),
+            );
+            let touching = context
+                .narrow_phase
+                .contact_pair(projectile, wall)
+                .map_or(false, |pair| pair.has_any_active_contact);
+            if touching {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn stricter_physics_settings_stop_fast_projectiles_tunneling() {
+        let loose = PhysicsSettings {
+            substeps: 1,
+            ccd: false,
+            max_ccd_substeps: 1,
+            ..Default::default()
+        };
+        assert!(!hits_thin_collider(loose));
+
+        let strict = PhysicsSettings {
+            substeps: 4,
+            ccd: true,
+            max_ccd_substeps: 4,
+            ..Default::default()
+        };
+        assert!(hits_thin_collider(strict));
+    }
+
+    #[test]
+    fn reflect_keeps_the_angle_of_a_45_degree_shot() {
+        let incoming = Vec3::new(-1.0, 0.0, -1.0).normalize() * PROJECTILE_SPEED;
+        let normal = Vec3::X;
+        let outgoing = reflect(incoming, normal);
+
+        assert!(outgoing.abs_diff_eq(
+            Vec3::new(1.0, 0.0, -1.0).normalize() * PROJECTILE_SPEED,
+            1e-4
+        ));
+        let angle = |v: Vec3| v.angle_between(normal);
+        assert!((angle(-incoming) - angle(outgoing)).abs() < 1e-4);
+        assert!((outgoing.length() - incoming.length()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reflect_matches_flip_on_side_walls() {
+        let velocity = Vec3::new(3.0, 0.0, -7.0);
+        assert_eq!(reflect(velocity, -Vec3::X), Vec3::new(-3.0, 0.0, -7.0));
+    }
+
+    fn turn(turn: u32, species: Species, score: u32) -> gameplay::TurnRecord {
+        gameplay::TurnRecord {
+            turn,
+            hex: hex::Coord::new(0, 0),
+            species,
+            cleared: score,
+            score,
+            diff: Default::default(),
+        }
+    }
+
+    #[test]
+    fn color_is_kept_until_a_shot_scores() {
+        let settings = gameplay::GameplaySettings {
+            keep_color_until_score: true,
+            ..Default::default()
+        };
+        let mut history = gameplay::TurnHistory::default();
+        assert_eq!(kept_species(&settings, &history), None);
+
+        history.push(turn(1, Species::Red, 0));
+        assert_eq!(kept_species(&settings, &history), Some(Species::Red));
+        history.push(turn(2, Species::Red, 0));
+        assert_eq!(kept_species(&settings, &history), Some(Species::Red));
+
+        history.push(turn(3, Species::Red, 3));
+        assert_eq!(kept_species(&settings, &history), None);
+    }
+
+    #[test]
+    fn color_is_not_kept_when_disabled() {
+        let settings = gameplay::GameplaySettings::default();
+        let history = gameplay::TurnHistory(vec![turn(1, Species::Red, 0)]);
+        assert_eq!(kept_species(&settings, &history), None);
+    }
+
+    #[test]
+    fn opening_shots_follow_the_level_script() {
+        use Species::*;
+
+        let script = vec![Yellow, Yellow, White, Blue, Red];
+        let mut world = World::new();
+        world.insert_resource(ResumeGame::default());
+        world.insert_resource(grid::GridSettings {
+            projectiles: script.clone(),
+            ..Default::default()
+        });
+        world.insert_resource(ProjectileBuffer::new(3));
+
+        SystemStage::single_threaded()
+            .with_system(restore_projectile_buffer)
+            .run(&mut world);
+
+        // reload the way projectile_reload does, falling back to random species
+        let grid = grid::Grid::default();
+        let mut buffer = world.resource_mut::<ProjectileBuffer>();
+        let shots = (0..script.len() + 2)
+            .map(|_| {
+                let species = buffer.queue.pop_front().unwrap();
+                buffer.refill(|| random_live_species(&grid));
+                species
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(shots[..script.len()], script[..]);
+        assert_eq!(buffer.queue.len(), 3);
+    }
+
+    #[test]
+    fn assist_loads_a_color_on_the_board() {
+        let mut grid = grid::Grid::default();
+        grid.live_species = HashSet::from([Species::Red]);
+        let mut buffer = ProjectileBuffer::new(2);
+        buffer.queue = VecDeque::from([Species::Green, Species::White]);
+
+        assert_eq!(usable_species(Species::Blue, &buffer, &grid), Species::Red);
+
+        // a usable next ball is enough
+        buffer.queue[0] = Species::Red;
+        assert_eq!(usable_species(Species::Blue, &buffer, &grid), Species::Blue);
+    }
+
+    #[test]
+    fn cleanup_despawns_every_projectile() {
+        let mut world = World::new();
+        let mut buffer = ProjectileBuffer::new(3);
+        buffer.queue = VecDeque::from([Species::Red, Species::Blue]);
+        world.insert_resource(buffer);
+        for _ in 0..2 {
+            world.spawn().insert(Projectile);
+        }
+
+        SystemStage::single_threaded()
+            .with_system(cleanup_projectile)
+            .run(&mut world);
+
+        assert_eq!(world.query::<&Projectile>().iter(&world).count(), 0);
+        assert!(world.resource::<ProjectileBuffer>().queue.is_empty());
+    }
+
+    #[test]
+    fn restitution_slows_the_bounce() {
+        let incoming = Vec3::new(-20.0, 0.0, -20.0);
+        for mode in [BounceMode::Flip, BounceMode::Reflect] {
+            let elastic = bounce_off_wall(incoming, Vec3::X, mode, 1.0);
+            assert_eq!(elastic, Vec3::new(20.0, 0.0, -20.0));
+
+            let damped = bounce_off_wall(incoming, Vec3::X, mode, 0.5);
+            assert_eq!(damped, Vec3::new(10.0, 0.0, -20.0));
+            assert!(damped.length() < elastic.length());
+        }
+    }
+
+    #[test]
+    fn aim_and_snap_follow_a_moved_shooter() {
+        let shooter = gameplay::ShooterPosition(Vec3::new(3.0, 0.0, 30.0));
+        let target = pivot_point(shooter.0, 0.0);
+        assert!((target.x - shooter.x).abs() < 1e-4);
+        assert!(target.z < shooter.z);
+
+        let mut grid = grid::Grid::default();
+        for hex in hex::rectangle(8, 4, &grid.layout) {
+            grid.set(hex, Some(Entity::from_raw(0)));
+        }
+        grid.update_bounds();
+
+        let radius = grid.layout.radius() * ball::BALL_RADIUS_COEFF;
+        let path = predict_path(&grid, shooter.0, target - shooter.0, radius, 0);
+        let landing = *path.last().unwrap();
+        assert!((landing.x - shooter.x).abs() < 1e-3);
+
+        // snaps to a free cell hanging from the grid
+        let hex = grid.layout.from_world(landing);
+        assert!(grid.get(hex).is_none());
+        assert!(!grid.neighbors(hex).is_empty());
+    }
+
+    #[test]
+    fn reticle_shows_the_landing_point() {
+        let mut grid = grid::Grid::default();
+        for hex in hex::rectangle(8, 4, &grid.layout) {
+            grid.set(hex, Some(Entity::from_raw(0)));
+        }
+        grid.update_bounds();
+
+        let shooter = Vec3::new(3.0, 0.0, 30.0);
+        let target = pivot_point(shooter, 0.2);
+        let radius = grid.layout.radius() * ball::BALL_RADIUS_COEFF * PROJ_COLLIDER_COEFF;
+        let colors = AimLineColors::default();
+        let path = predict_path(&grid, shooter, target - shooter, radius, colors.0.len() - 1);
+        let landing = *path.last().unwrap();
+
+        let mut world = World::new();
+        world.insert_resource(grid);
+        world.insert_resource(colors);
+        world.insert_resource(AimTarget(Some(target)));
+        world
+            .spawn()
+            .insert(Projectile)
+            .insert(Flying(false))
+            .insert(Transform::from_translation(shooter));
+        let reticle = world
+            .spawn()
+            .insert(Reticle)
+            .insert(Transform::default())
+            .insert(Visibility { is_visible: false })
+            .id();
+        let mut stage = SystemStage::single_threaded().with_system(update_reticle);
+
+        stage.run(&mut world);
+        assert_eq!(
+            world.get::<Transform>(reticle).unwrap().translation,
+            landing
+        );
+        assert_ne!(landing, target);
+        assert!(world.get::<Visibility>(reticle).unwrap().is_visible);
+
+        // hidden while nothing is loaded
+        world.resource_mut::<AimTarget>().0 = None;
+        stage.run(&mut world);
+        assert!(!world.get::<Visibility>(reticle).unwrap().is_visible);
+    }
+
+    fn reload_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>()
+            .add_event::<gameplay::BeginTurn>()
+            .insert_resource(crate::loading::TextureAssets {
+                texture_bevy: Handle::default(),
+            })
+            .init_resource::<ball::SpeciesMesh>()
+            .init_resource::<ball::BallMaterials>()
+            .init_resource::<ProjectileBuffer>()
+            .init_resource::<gameplay::TurnPhase>()
+            .init_resource::<PhysicsSettings>()
+            .init_resource::<gameplay::GameplaySettings>()
+            .init_resource::<gameplay::TurnHistory>()
+            .init_resource::<grid::Grid>()
+            .init_resource::<gameplay::ShooterPosition>()
+            .init_resource::<ForceNextProjectile>()
+            .init_resource::<gameplay::GameOverFade>()
+            .add_system(projectile_reload);
+        app
+    }
+
+    fn reload(app: &mut App) -> Species {
+        app.world
+            .resource_mut::<Events<gameplay::BeginTurn>>()
+            .send(gameplay::BeginTurn);
+        app.update();
+
+        let mut loaded = app
+            .world
+            .query_filtered::<(Entity, &Species), With<Projectile>>();
+        let (entity, species) = loaded.single(&app.world);
+        let species = *species;
+        app.world.despawn(entity);
+        species
+    }
+
+    #[test]
+    fn forced_species_is_loaded_once() {
+        let mut app = reload_app();
+        app.world.resource_mut::<ProjectileBuffer>().queue =
+            VecDeque::from([Species::Red, Species::Blue, Species::Green]);
+        app.world.resource_mut::<ForceNextProjectile>().0 = Some(Species::Yellow);
+
+        assert_eq!(reload(&mut app), Species::Yellow);
+        assert!(app.world.resource::<ForceNextProjectile>().0.is_none());
+        // the buffer is left untouched by the forced shot
+        assert_eq!(reload(&mut app), Species::Red);
+        assert_eq!(reload(&mut app), Species::Blue);
+    }
+
+    #[test]
+    fn snaps_against_the_closest_of_several_contacts() {
+        use bevy_rapier3d::rapier::geometry::CollisionEventFlags;
+
+        let mut world = World::new();
+        world.insert_resource(Events::<CollisionEvent>::default());
+        world.insert_resource(Events::<SnapProjectile>::default());
+        let projectile = world
+            .spawn()
+            .insert(Projectile)
+            .insert(Flying(true))
+            .insert(Velocity::linear(Vec3::new(0.0, 0.0, -PROJECTILE_SPEED)))
+            .insert(Transform::from_xyz(0.0, 0.0, 10.0))
+            .id();
+        let mut ball_at = |x: f32, z: f32| {
+            world
+                .spawn()
+                .insert(ball::Ball)
+                .insert(Transform::from_xyz(x, 0.0, z))
+                .id()
+        };
+        let grazed = ball_at(1.8, 9.5);
+        let nearest = ball_at(0.2, 8.8);
+
+        let mut events = world.resource_mut::<Events<CollisionEvent>>();
+        // the grazing contact is reported first, in either order of the pair
+        events.send(CollisionEvent::Started(
+            grazed,
+            projectile,
+            CollisionEventFlags::empty(),
+        ));
+        events.send(CollisionEvent::Started(
+            projectile,
+            nearest,
+            CollisionEventFlags::empty(),
+        ));
+
+        SystemStage::single_threaded()
+            .with_system(on_projectile_collisions_events)
+            .run(&mut world);
+
+        let snaps = world.resource::<Events<SnapProjectile>>();
+        let snapped = snaps
+            .get_reader()
+            .iter(snaps)
+            .map(|snap| snap.entity)
+            .collect::<Vec<_>>();
+        assert_eq!(snapped, vec![Some(nearest)]);
+        assert_eq!(
+            world.get::<Velocity>(projectile).unwrap().linvel,
+            Vec3::ZERO
+        );
+    }
+
+    /// [reload_app] that also aims and fires, without a window it aims straight ahead.
+    fn fire_app() -> App {
+        let mut app = reload_app();
+        app.init_resource::<Windows>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<AimMode>()
+            .init_resource::<AimTarget>()
+            .init_resource::<ChargedShot>()
+            .init_resource::<PowerShot>()
+            .init_resource::<QueuedFire>()
+            .init_resource::<AutoFire>()
+            .init_resource::<Paused>()
+            .init_resource::<gameplay::DescentTimer>()
+            .init_resource::<bevy_kira_audio::Audio>()
+            .insert_resource(AudioAssets {
+                flying: Handle::default(),
+                soundtrack: Handle::default(),
+                score: Handle::default(),
+                settle: Handle::default(),
+            })
+            .add_system(aim_projectile.after(projectile_reload));
+        app
+    }
+
+    fn begin_turn(app: &mut App) {
+        app.world
+            .resource_mut::<Events<gameplay::BeginTurn>>()
+            .send(gameplay::BeginTurn);
+    }
+
+    /// Returns the projectiles fired since the last call, despawning them as if they snapped.
+    fn take_fired(app: &mut App) -> usize {
+        let mut projectiles = app
+            .world
+            .query_filtered::<(Entity, &Flying), With<Projectile>>();
+        let fired = projectiles
+            .iter(&app.world)
+            .filter(|(_, flying)| flying.0)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for &entity in fired.iter() {
+            app.world.despawn(entity);
+        }
+        fired.len()
+    }
+
+    #[test]
+    fn early_click_fires_the_next_projectile() {
+        let fired = |enabled: bool| {
+            let mut app = fire_app();
+            app.world.resource_mut::<QueuedFire>().enabled = enabled;
+
+            // clicked while the previous shot resolves, nothing is loaded yet
+            *app.world.resource_mut::<gameplay::TurnPhase>() = gameplay::TurnPhase::Resolving;
+            app.world
+                .resource_mut::<Input<MouseButton>>()
+                .press(MouseButton::Left);
+            app.update();
+            let mut mouse = app.world.resource_mut::<Input<MouseButton>>();
+            mouse.release(MouseButton::Left);
+            mouse.clear();
+            assert_eq!(take_fired(&mut app), 0);
+
+            // loads the projectile, it's aimed from the next frame on
+            begin_turn(&mut app);
+            app.update();
+            app.update();
+            take_fired(&mut app)
+        };
+
+        assert_eq!(fired(true), 1);
+        assert_eq!(fired(false), 0);
+    }
+
+    #[test]
+    fn auto_fire_shoots_once_per_reload_while_held() {
+        let shots = |enabled: bool| {
+            let mut app = fire_app();
+            app.world.resource_mut::<AutoFire>().enabled = enabled;
+            begin_turn(&mut app);
+            app.update();
+
+            app.world
+                .resource_mut::<Input<MouseButton>>()
+                .press(MouseButton::Left);
+            let mut shots = 0;
+            for _frame in 0..10 {
+                app.update();
+                // still held, but no longer just pressed
+                app.world.resource_mut::<Input<MouseButton>>().clear();
+
+                let fired = take_fired(&mut app);
+                assert!(fired <= 1);
+                if fired > 0 {
+                    shots += fired;
+                    // snapped and resolved at once
+                    *app.world.resource_mut::<gameplay::TurnPhase>() =
+                        gameplay::TurnPhase::Resolving;
+                    begin_turn(&mut app);
+                }
+            }
+            shots
+        };
+
+        // fired on every other frame, the ones in between load the next projectile
+        assert_eq!(shots(true), 5);
+        assert_eq!(shots(false), 1);
+    }
+
+    /// Counts frames in the regular update stage, which keeps running while paused.
+    #[derive(Default)]
+    struct Frames(u32);
+
+    fn count_frames(mut frames: ResMut<Frames>) {
+        frames.0 += 1;
+    }
+
+    #[test]
+    fn pause_freezes_physics_but_not_the_app() {
+        use bevy_rapier3d::rapier::geometry::CollisionEventFlags;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<CollisionEvent>()
+            .add_event::<SnapProjectile>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<Paused>()
+            .init_resource::<Frames>()
+            .insert_resource(RapierConfiguration::default())
+            // a turn waiting on nothing but the next frame
+            .init_resource::<gameplay::ClearQueue>()
+            .insert_resource(gameplay::ResolveDelay(Some(Timer::from_seconds(
+                0.0, false,
+            ))))
+            .insert_resource(gameplay::TurnPhase::Resolving)
+            .add_event::<gameplay::BeginTurn>()
+            .init_resource::<gameplay::GameOverFade>()
+            .insert_resource(ReduceMotion(false))
+            .add_system(toggle_pause)
+            .add_system(gameplay::finish_resolving.after(toggle_pause))
+            .add_system(count_frames)
+            .add_stage_after(
+                CoreStage::Update,
+                ProjectileStage::Update,
+                SystemStage::single_threaded(),
+            )
+            .add_system_set_to_stage(
+                ProjectileStage::Update,
+                SystemSet::new()
+                    .with_run_criteria(run_if_not_paused)
+                    .with_system(on_projectile_collisions_events),
+            );
+
+        let projectile = app
+            .world
+            .spawn()
+            .insert(Projectile)
+            .insert(Flying(true))
+            .insert(Velocity::linear(Vec3::new(0.0, 0.0, -PROJECTILE_SPEED)))
+            .insert(Transform::default())
+            .id();
+        let ball = app
+            .world
+            .spawn()
+            .insert(ball::Ball)
+            .insert(Transform::from_xyz(0.0, 0.0, -1.0))
+            .id();
+
+        let pause = KeyCode::P;
+        let press = |app: &mut App| {
+            let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+            keys.release(pause);
+            keys.clear();
+            keys.press(pause);
+        };
+        let hit = |app: &mut App| {
+            app.world
+                .resource_mut::<Events<CollisionEvent>>()
+                .send(CollisionEvent::Started(
+                    projectile,
+                    ball,
+                    CollisionEventFlags::empty(),
+                ));
+            app.update();
+            // there's no input plugin to clear the press each frame
+            app.world.resource_mut::<Input<KeyCode>>().clear();
+            let snaps = app.world.resource::<Events<SnapProjectile>>();
+            snaps.get_reader().iter(snaps).count()
+        };
+
+        press(&mut app);
+        assert_eq!(hit(&mut app), 0);
+        assert!(app.world.resource::<Paused>().0);
+        assert!(
+            !app.world
+                .resource::<RapierConfiguration>()
+                .physics_pipeline_active
+        );
+        assert_eq!(hit(&mut app), 0);
+        assert_eq!(app.world.resource::<Frames>().0, 2);
+        assert_eq!(
+            *app.world.resource::<gameplay::TurnPhase>(),
+            gameplay::TurnPhase::Resolving
+        );
+        let velocity = app.world.get::<Velocity>(projectile).unwrap();
+        assert_eq!(velocity.linvel, Vec3::new(0.0, 0.0, -PROJECTILE_SPEED));
+
+        press(&mut app);
+        assert_eq!(hit(&mut app), 1);
+        assert_eq!(
+            *app.world.resource::<gameplay::TurnPhase>(),
+            gameplay::TurnPhase::Ready
+        );
+        assert!(
+            app.world
+                .resource::<RapierConfiguration>()
+                .physics_pipeline_active
+        );
+        assert_eq!(
+            app.world.get::<Velocity>(projectile).unwrap().linvel,
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn power_shot_speed_spans_min_to_max_power() {
+        let power_shot = PowerShot::default();
+        assert_eq!(power_shot.speed(0.0), power_shot.min_speed);
+        assert_eq!(power_shot.speed(1.0), power_shot.max_speed);
+        assert_eq!(power_shot.speed(0.5), 30.0);
+        // power outside the meter is clamped
+        assert_eq!(power_shot.speed(-1.0), power_shot.min_speed);
+        assert_eq!(power_shot.speed(2.0), power_shot.max_speed);
+
+        // speeds the physics can't handle are clamped too
+        let extreme = PowerShot {
+            min_speed: 0.0,
+            max_speed: 500.0,
+            ..Default::default()
+        };
+        assert_eq!(extreme.speed(0.0), MIN_PROJECTILE_SPEED);
+        assert_eq!(extreme.speed(1.0), MAX_PROJECTILE_SPEED);
     }
 }
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{self, Species},
+    gameplay::{GameMode, Hotseat, Lives, Score, TurnCounter, TurnHistory, TurnRecord},
+    grid, hex,
+    projectile::{Projectile, ProjectileBuffer},
+    storage, AppState,
+};
+
+const SAVE_KEY: &str = "ball_shooter_save";
+
+/// Snapshot of an in-progress game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedGame {
+    pub score: u32,
+    pub turn: u32,
+    /// Grid layout origin, boards aren't re-centered when resumed.
+    pub origin: [f32; 2],
+    /// Species of the loaded projectile.
+    pub projectile: Species,
+    /// Upcoming species, next one first.
+    pub buffer: Vec<Species>,
+    pub balls: Vec<(hex::Coord, Species)>,
+    #[serde(default)]
+    pub obstacles: Vec<hex::Coord>,
+    /// Armored balls and their remaining health.
+    #[serde(default)]
+    pub health: Vec<(hex::Coord, u32)>,
+    #[serde(default)]
+    pub mode: GameMode,
+    /// Whether the game is a [Hotseat] game.
+    #[serde(default)]
+    pub hotseat: bool,
+    /// Turns played so far, hotseat scores and streaks are derived from them.
+    #[serde(default)]
+    pub history: Vec<TurnRecord>,
+    /// [Lives] left, older saves resume with every life.
+    #[serde(default)]
+    pub lives: Option<u32>,
+}
+
+impl SavedGame {
+    /// Load the saved game, if there is one.
+    pub fn load() -> Option<Self> {
+        storage::load(SAVE_KEY)
+    }
+}
+
+/// Game to restore when entering [AppState::Gameplay], `None` starts a new game.
+#[derive(Default, Debug, Clone)]
+pub struct ResumeGame(pub Option<SavedGame>);
+
+/// Saves the game every time a new projectile is loaded, practice games aren't saved.
+fn autosave(
+    projectile: Query<&Species, Added<Projectile>>,
+    balls: Query<(&hex::Coord, &Species), With<ball::Ball>>,
+    obstacles: Query<&hex::Coord, With<ball::Obstacle>>,
+    grid: Res<grid::Grid>,
+    score: Res<Score>,
+    turn_counter: Res<TurnCounter>,
+    buffer: Res<ProjectileBuffer>,
+    mode: Res<GameMode>,
+    hotseat: Res<Hotseat>,
+    history: Res<TurnHistory>,
+    lives: Res<Lives>,
+) {
+    if *mode == GameMode::Practice {
+        return;
+    }
+
+    if let Ok(species) = projectile.get_single() {
+        storage::save(
+            SAVE_KEY,
+            &SavedGame {
+                score: score.0,
+                turn: turn_counter.0,
+                origin: grid.layout.origin.into(),
+                projectile: *species,
+                buffer: buffer.queue.iter().copied().collect(),
+                balls: balls
+                    .iter()
+                    .map(|(&hex, &species)| (hex, species))
+                    .collect(),
+                obstacles: obstacles.iter().copied().collect(),
+                health: grid
+                    .health
+                    .iter()
+                    .filter_map(|(&entity, &health)| {
+                        balls.get(entity).ok().map(|(&hex, _)| (hex, health))
+                    })
+                    .collect(),
+                mode: *mode,
+                hotseat: hotseat.enabled,
+                history: history.0.clone(),
+                lives: Some(lives.left()),
+            },
+        );
+    }
+}
+
+fn delete_save() {
+    storage::remove(SAVE_KEY);
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResumeGame>();
+        app.add_system_set(SystemSet::on_update(AppState::Gameplay).with_system(autosave));
+        app.add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(delete_save));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_game_round_trip() {
+        let saved = SavedGame {
+            score: 42,
+            turn: 7,
+            origin: [-7.5, 0.0],
+            projectile: Species::Blue,
+            buffer: vec![Species::Red, Species::White],
+            balls: vec![
+                (hex::Coord::new(0, 0), Species::Green),
+                (hex::Coord::new(1, 2), Species::Yellow),
+            ],
+            obstacles: vec![hex::Coord::new(3, 0)],
+            health: vec![(hex::Coord::new(1, 2), 2)],
+            mode: GameMode::SuddenDeath,
+            hotseat: true,
+            history: vec![TurnRecord {
+                turn: 6,
+                hex: hex::Coord::new(2, 1),
+                species: Species::Red,
+                cleared: 3,
+                score: 3,
+                diff: grid::GridDiff {
+                    added: vec![],
+                    removed: vec![(hex::Coord::new(2, 1), Species::Red)],
+                },
+            }],
+            lives: Some(2),
+        };
+
+        let json = serde_json::to_string(&saved).unwrap();
+        assert_eq!(serde_json::from_str::<SavedGame>(&json).unwrap(), saved);
+    }
+
+    #[test]
+    fn older_saves_start_a_normal_game() {
+        let json = r#"{
+            "score": 1,
+            "turn": 2,
+            "origin": [0.0, 0.0],
+            "projectile": "Red",
+            "buffer": [],
+            "balls": []
+        }"#;
+        let saved = serde_json::from_str::<SavedGame>(json).unwrap();
+        assert_eq!(saved.mode, GameMode::Normal);
+        assert!(!saved.hotseat);
+        assert!(saved.history.is_empty());
+        assert_eq!(saved.lives, None);
+    }
+}
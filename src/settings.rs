@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gameplay::{GhostRun, Lives},
+    projectile::{AimMode, BounceMode, ChargedShot, ProjectileTrail},
+    storage,
+    theme::FontScale,
+    MsaaSamples, ReduceMotion,
+};
+
+const SETTINGS_KEY: &str = "ball_shooter_settings";
+
+/// Most lives that can be picked in the settings menu.
+pub const MAX_LIVES: u32 = 5;
+
+/// Options changed from the settings menu, persisted between sessions and applied to the
+/// resources they control whenever they change.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// See [ReduceMotion].
+    pub reduce_motion: bool,
+    /// See [GhostRun].
+    pub ghost_run: bool,
+    /// Aim with [AimMode::Pivot] instead of [AimMode::Point].
+    pub pivot_aim: bool,
+    /// See [ChargedShot].
+    pub charged_shot: bool,
+    /// See [ProjectileTrail].
+    pub trail: bool,
+    /// See [FontScale].
+    pub font_scale: FontScale,
+    /// See [BounceMode].
+    pub bounce_mode: BounceMode,
+    /// [Lives::max], `1..=MAX_LIVES`.
+    pub lives: u32,
+    /// See [MsaaSamples].
+    pub msaa_samples: MsaaSamples,
+}
+
+impl Settings {
+    /// Load the saved settings, defaults if none were saved.
+    pub fn load() -> Self {
+        storage::load(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_KEY, self);
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            reduce_motion: false,
+            ghost_run: false,
+            pivot_aim: false,
+            charged_shot: false,
+            trail: false,
+            font_scale: FontScale::Medium,
+            bounce_mode: BounceMode::Flip,
+            lives: 1,
+            msaa_samples: MsaaSamples::default(),
+        }
+    }
+}
+
+fn load_settings(mut commands: Commands) {
+    commands.insert_resource(Settings::load());
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut reduce_motion: ResMut<ReduceMotion>,
+    mut ghost_run: ResMut<GhostRun>,
+    mut aim_mode: ResMut<AimMode>,
+    mut charged_shot: ResMut<ChargedShot>,
+    mut projectile_trail: ResMut<ProjectileTrail>,
+    mut font_scale: ResMut<FontScale>,
+    mut bounce_mode: ResMut<BounceMode>,
+    mut lives: ResMut<Lives>,
+    mut msaa_samples: ResMut<MsaaSamples>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    reduce_motion.0 = settings.reduce_motion;
+    ghost_run.enabled = settings.ghost_run;
+    *aim_mode = match settings.pivot_aim {
+        true => AimMode::pivot(),
+        false => AimMode::Point,
+    };
+    charged_shot.enabled = settings.charged_shot;
+    projectile_trail.enabled = settings.trail;
+    *font_scale = settings.font_scale;
+    *bounce_mode = settings.bounce_mode;
+    lives.max = settings.lives.clamp(1, MAX_LIVES);
+    *msaa_samples = settings.msaa_samples;
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_settings);
+        app.add_system(apply_settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_settings_keep_their_defaults() {
+        let settings = serde_json::from_str::<Settings>("{}").unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn changed_settings_are_applied() {
+        let mut world = World::new();
+        world.insert_resource(Settings::default());
+        world.insert_resource(ReduceMotion(false));
+        world.insert_resource(GhostRun::default());
+        world.insert_resource(AimMode::default());
+        world.insert_resource(ChargedShot::default());
+        world.insert_resource(ProjectileTrail::default());
+        world.insert_resource(FontScale::default());
+        world.insert_resource(BounceMode::default());
+        world.insert_resource(Lives::default());
+        world.insert_resource(MsaaSamples::default());
+        let mut stage = SystemStage::single_threaded().with_system(apply_settings);
+        stage.run(&mut world);
+        assert!(!world.resource::<ReduceMotion>().0);
+
+        world.resource_mut::<Settings>().reduce_motion = true;
+        world.resource_mut::<Settings>().ghost_run = true;
+        world.resource_mut::<Settings>().pivot_aim = true;
+        world.resource_mut::<Settings>().charged_shot = true;
+        world.resource_mut::<Settings>().trail = true;
+        world.resource_mut::<Settings>().font_scale = FontScale::Large;
+        world.resource_mut::<Settings>().bounce_mode = BounceMode::Reflect;
+        world.resource_mut::<Settings>().lives = 3;
+        world.resource_mut::<Settings>().msaa_samples = MsaaSamples(1);
+        stage.run(&mut world);
+        assert!(world.resource::<ReduceMotion>().0);
+        assert!(world.resource::<GhostRun>().enabled);
+        assert_eq!(*world.resource::<AimMode>(), AimMode::pivot());
+        assert!(world.resource::<ChargedShot>().enabled);
+        assert!(world.resource::<ProjectileTrail>().enabled);
+        assert_eq!(*world.resource::<FontScale>(), FontScale::Large);
+        assert_eq!(*world.resource::<BounceMode>(), BounceMode::Reflect);
+        assert_eq!(world.resource::<Lives>().max, 3);
+        assert_eq!(*world.resource::<MsaaSamples>(), MsaaSamples(1));
+    }
+}
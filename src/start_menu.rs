@@ -1,68 +1,301 @@
-use std::time::Duration;
-
-use crate::loading::{AudioAssets, FontAssets};
+use crate::bindings::{Action, Binding, KeyBindings};
+use crate::gameplay::{GameMode, Hotseat};
+use crate::leaderboard::{spawn_leaderboard, Leaderboard};
+use crate::loading::FontAssets;
+use crate::localization::Localization;
+use crate::navigation::{nav_input, reset_menu_navigation, KeepMenuFocus, MenuFocus, NavInput};
+use crate::save::{ResumeGame, SavedGame};
+use crate::settings::{Settings, MAX_LIVES};
+use crate::theme::{FontScale, Theme};
+use crate::tutorial::{mark_tutorial_seen, reset_tutorial, tutorial_seen};
 use crate::AppState;
-use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use std::marker::PhantomData;
+
+#[derive(Component)]
+struct Root;
 
-struct ButtonColors {
-    normal: UiColor,
-    hovered: UiColor,
+/// Menu buttons, ordered top to bottom.
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MenuButton {
+    Play,
+    TwoPlayers,
+    Practice,
+    SuddenDeath,
+    Timed,
+    Continue,
+    Language,
+    Settings,
+    Controls,
+    ReduceMotion,
+    GhostRun,
+    AimMode,
+    ChargedShot,
+    ProjectileTrail,
+    FontScale,
+    BounceMode,
+    ReplayTutorial,
+    Lives,
+    Msaa,
+    Rebind(Action),
+    Back,
 }
 
-impl Default for ButtonColors {
-    fn default() -> Self {
-        ButtonColors {
-            normal: Color::rgb(0.15, 0.15, 0.15).into(),
-            hovered: Color::rgb(0.25, 0.25, 0.25).into(),
+/// Page of the menu that is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum MenuPage {
+    #[default]
+    Main,
+    Settings,
+    Controls,
+}
+
+impl MenuPage {
+    /// Returns the page the back button leads to.
+    fn back(self) -> Self {
+        match self {
+            MenuPage::Main | MenuPage::Settings => MenuPage::Main,
+            MenuPage::Controls => MenuPage::Settings,
         }
     }
 }
 
-struct SoundtrackAudio(Handle<AudioInstance>);
+/// Action waiting for the next key or mouse press to be bound to it.
+#[derive(Debug, Clone, Default)]
+struct Rebinding(Option<Action>);
 
-fn start_audio(
-    mut commands: Commands,
-    audio_assets: Res<AudioAssets>,
-    audio: Res<bevy_kira_audio::Audio>,
-) {
-    audio.pause();
-    let handle = audio
-        .play(audio_assets.soundtrack.clone())
-        .looped()
-        .fade_in(AudioTween::linear(Duration::from_secs(5)))
-        .with_volume(0.4)
-        .handle();
-
-    commands.insert_resource(SoundtrackAudio(handle));
+/// Main menu buttons are large, option pages fit more rows with smaller ones.
+#[derive(Debug, Clone, Copy)]
+enum ButtonSize {
+    Large,
+    Small,
 }
 
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
-    button_colors: Res<ButtonColors>,
+    theme: Res<Theme>,
+    leaderboard: Res<Leaderboard>,
+    localization: Res<Localization>,
+    font_scale: Res<FontScale>,
+    page: Res<MenuPage>,
+    rebinding: Res<Rebinding>,
+    bindings: Res<KeyBindings>,
+    settings: Res<Settings>,
+    focus: Res<MenuFocus>,
 ) {
     commands.spawn_bundle(Camera2dBundle::default());
+
+    let can_continue = SavedGame::load().is_some();
+
     commands
-        .spawn_bundle(ButtonBundle {
+        .spawn_bundle(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(120.0), Val::Px(50.0)),
                 margin: UiRect::all(Val::Auto),
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(Root)
+        .with_children(|parent| {
+            let toggle = |key: &str, enabled: bool| {
+                let value = match enabled {
+                    true => localization.get("on"),
+                    false => localization.get("off"),
+                };
+                localization.format("option", &[&localization.get(key), &value])
+            };
+            // buttons are spawned in navigation order
+            let mut index = 0;
+            let mut add = |button: MenuButton, label: &str, size: ButtonSize| {
+                spawn_button(
+                    parent,
+                    button,
+                    label,
+                    &font_assets,
+                    *font_scale,
+                    match index == focus.0 {
+                        true => theme.menu.button.hovered,
+                        false => theme.menu.button.normal,
+                    },
+                    size,
+                );
+                index += 1;
+            };
+
+            match *page {
+                MenuPage::Main => {
+                    for (menu_button, key) in [
+                        (MenuButton::Play, "play"),
+                        (MenuButton::TwoPlayers, "two_players"),
+                        (MenuButton::Practice, "practice"),
+                        (MenuButton::SuddenDeath, "sudden_death"),
+                        (MenuButton::Timed, "timed"),
+                    ] {
+                        add(menu_button, localization.get(key), ButtonSize::Large);
+                    }
+                    if can_continue {
+                        add(
+                            MenuButton::Continue,
+                            localization.get("continue"),
+                            ButtonSize::Large,
+                        );
+                    }
+                    add(
+                        MenuButton::Language,
+                        localization.get("language"),
+                        ButtonSize::Large,
+                    );
+                    add(
+                        MenuButton::Settings,
+                        localization.get("settings"),
+                        ButtonSize::Large,
+                    );
+                    spawn_leaderboard(parent, &leaderboard, &font_assets, *font_scale);
+                }
+                MenuPage::Settings => {
+                    add(
+                        MenuButton::Controls,
+                        localization.get("controls"),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::ReduceMotion,
+                        &toggle("reduce_motion", settings.reduce_motion),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::GhostRun,
+                        &toggle("ghost_run", settings.ghost_run),
+                        ButtonSize::Small,
+                    );
+                    let aim = match settings.pivot_aim {
+                        true => localization.get("aim_pivot"),
+                        false => localization.get("aim_point"),
+                    };
+                    add(
+                        MenuButton::AimMode,
+                        &localization.format("option", &[&localization.get("aim_mode"), &aim]),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::ChargedShot,
+                        &toggle("charged_shot", settings.charged_shot),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::ProjectileTrail,
+                        &toggle("trail", settings.trail),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::FontScale,
+                        &localization.format(
+                            "option",
+                            &[
+                                &localization.get("text_size"),
+                                &localization.get(settings.font_scale.key()),
+                            ],
+                        ),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::BounceMode,
+                        &localization.format(
+                            "option",
+                            &[
+                                &localization.get("bounce_mode"),
+                                &localization.get(settings.bounce_mode.key()),
+                            ],
+                        ),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::ReplayTutorial,
+                        &toggle("replay_tutorial", !tutorial_seen()),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::Lives,
+                        &localization
+                            .format("option", &[&localization.get("max_lives"), &settings.lives]),
+                        ButtonSize::Small,
+                    );
+                    let msaa = match settings.msaa_samples.supported() {
+                        1 => localization.get("off").to_string(),
+                        samples => localization.format("msaa_samples", &[&samples]),
+                    };
+                    add(
+                        MenuButton::Msaa,
+                        &localization.format("option", &[&localization.get("msaa"), &msaa]),
+                        ButtonSize::Small,
+                    );
+                    add(
+                        MenuButton::Back,
+                        localization.get("back"),
+                        ButtonSize::Small,
+                    );
+                }
+                MenuPage::Controls => {
+                    for action in Action::all() {
+                        let name = localization.get(action.key());
+                        let label = match rebinding.0 == Some(*action) {
+                            true => localization.format("press_key", &[&name]),
+                            false => {
+                                localization.format("binding", &[&name, &bindings.get(*action)])
+                            }
+                        };
+                        add(MenuButton::Rebind(*action), &label, ButtonSize::Small);
+                    }
+                    add(
+                        MenuButton::Back,
+                        localization.get("back"),
+                        ButtonSize::Small,
+                    );
+                }
+            }
+        });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    button: MenuButton,
+    label: &str,
+    font_assets: &FontAssets,
+    font_scale: FontScale,
+    color: UiColor,
+    size: ButtonSize,
+) {
+    let (width, height, font_size) = match size {
+        ButtonSize::Large => (180.0, 50.0, 40.0),
+        ButtonSize::Small => (360.0, 36.0, 24.0),
+    };
+
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(height)),
+                margin: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..Default::default()
             },
-            color: button_colors.normal,
+            color,
             ..Default::default()
         })
+        .insert(button)
         .with_children(|parent| {
             parent.spawn_bundle(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: "Play".to_string(),
+                        value: label.to_string(),
                         style: TextStyle {
                             font: font_assets.fira_sans.clone(),
-                            font_size: 40.0,
+                            font_size: font_scale.size(font_size),
                             color: Color::rgb(0.9, 0.9, 0.9),
                         },
                     }],
@@ -73,35 +306,236 @@ fn setup_menu(
         });
 }
 
+/// Resources changed by activating a menu button.
+#[derive(SystemParam)]
+struct MenuActions<'w, 's> {
+    state: ResMut<'w, State<AppState>>,
+    resume: ResMut<'w, ResumeGame>,
+    hotseat: ResMut<'w, Hotseat>,
+    mode: ResMut<'w, GameMode>,
+    localization: ResMut<'w, Localization>,
+    page: ResMut<'w, MenuPage>,
+    rebinding: ResMut<'w, Rebinding>,
+    settings: ResMut<'w, Settings>,
+    keep_focus: ResMut<'w, KeepMenuFocus>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
 fn click_play_button(
-    button_colors: Res<ButtonColors>,
-    mut state: ResMut<State<AppState>>,
+    theme: Res<Theme>,
+    mut menu: MenuActions,
     mut interaction_query: Query<
-        (&Interaction, &mut UiColor),
+        (&Interaction, &mut UiColor, &MenuButton),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color) in &mut interaction_query {
+    if menu.rebinding.0.is_some() {
+        return;
+    }
+
+    for (interaction, mut color, button) in &mut interaction_query {
         match *interaction {
             Interaction::Clicked => {
-                state.set(AppState::Gameplay).unwrap();
+                activate_button(*button, &mut menu);
             }
             Interaction::Hovered => {
-                *color = button_colors.hovered;
+                *color = theme.menu.button.hovered;
             }
             Interaction::None => {
-                *color = button_colors.normal;
+                *color = theme.menu.button.normal;
+            }
+        }
+    }
+}
+
+fn navigate_menu(
+    keys: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    theme: Res<Theme>,
+    mut focus: ResMut<MenuFocus>,
+    mut menu: MenuActions,
+    mut buttons: Query<(&MenuButton, &mut UiColor)>,
+) {
+    if menu.rebinding.0.is_some() {
+        return;
+    }
+
+    let input = match nav_input(&keys, &gamepad_buttons) {
+        Some(input) => input,
+        None => return,
+    };
+
+    let mut buttons = buttons.iter_mut().collect::<Vec<_>>();
+    buttons.sort_by_key(|(button, _)| **button);
+    focus.step(input, buttons.len());
+
+    if input == NavInput::Activate {
+        if let Some((button, _)) = buttons.get(focus.0) {
+            activate_button(**button, &mut menu);
+        }
+        return;
+    }
+
+    for (i, (_, color)) in buttons.iter_mut().enumerate() {
+        **color = match i == focus.0 {
+            true => theme.menu.button.hovered,
+            false => theme.menu.button.normal,
+        };
+    }
+}
+
+fn activate_button(button: MenuButton, menu: &mut MenuActions) {
+    let page = match button {
+        MenuButton::Language => {
+            let language = menu.localization.language.next();
+            menu.localization.set_language(language);
+            None
+        }
+        MenuButton::Settings => Some(MenuPage::Settings),
+        MenuButton::Controls => Some(MenuPage::Controls),
+        MenuButton::Back => Some(menu.page.back()),
+        MenuButton::Rebind(action) => {
+            menu.rebinding.0 = Some(action);
+            None
+        }
+        MenuButton::ReduceMotion => {
+            menu.settings.reduce_motion = !menu.settings.reduce_motion;
+            menu.settings.save();
+            None
+        }
+        MenuButton::GhostRun => {
+            menu.settings.ghost_run = !menu.settings.ghost_run;
+            menu.settings.save();
+            None
+        }
+        MenuButton::AimMode => {
+            menu.settings.pivot_aim = !menu.settings.pivot_aim;
+            menu.settings.save();
+            None
+        }
+        MenuButton::ChargedShot => {
+            menu.settings.charged_shot = !menu.settings.charged_shot;
+            menu.settings.save();
+            None
+        }
+        MenuButton::ProjectileTrail => {
+            menu.settings.trail = !menu.settings.trail;
+            menu.settings.save();
+            None
+        }
+        MenuButton::FontScale => {
+            menu.settings.font_scale = menu.settings.font_scale.next();
+            menu.settings.save();
+            None
+        }
+        MenuButton::BounceMode => {
+            menu.settings.bounce_mode = menu.settings.bounce_mode.next();
+            menu.settings.save();
+            None
+        }
+        MenuButton::ReplayTutorial => {
+            match tutorial_seen() {
+                true => reset_tutorial(),
+                false => mark_tutorial_seen(),
             }
+            None
+        }
+        MenuButton::Lives => {
+            menu.settings.lives = menu.settings.lives % MAX_LIVES + 1;
+            menu.settings.save();
+            None
+        }
+        MenuButton::Msaa => {
+            menu.settings.msaa_samples = menu.settings.msaa_samples.next();
+            menu.settings.save();
+            None
+        }
+        _ => {
+            start_game(button, menu);
+            return;
+        }
+    };
+    // options and rebinding stay focused, a new page starts at the top
+    match page {
+        Some(page) => *menu.page = page,
+        None => menu.keep_focus.0 = true,
+    }
+    // rebuild the menu with the new page or strings
+    menu.state.restart().unwrap();
+}
+
+fn start_game(button: MenuButton, menu: &mut MenuActions) {
+    // a continued game keeps the mode it was started in
+    let saved = match button {
+        MenuButton::Continue => SavedGame::load(),
+        _ => None,
+    };
+    menu.hotseat.enabled = match &saved {
+        Some(saved) => saved.hotseat,
+        None => matches!(button, MenuButton::TwoPlayers),
+    };
+    *menu.mode = match (&saved, button) {
+        (Some(saved), _) => saved.mode,
+        (None, MenuButton::Practice) => GameMode::Practice,
+        (None, MenuButton::SuddenDeath) => GameMode::SuddenDeath,
+        (None, MenuButton::Timed) => GameMode::Timed,
+        _ => GameMode::Normal,
+    };
+    menu.resume.0 = saved;
+    menu.state.set(AppState::Gameplay).unwrap();
+}
+
+/// Binds the action being rebound to the next key or mouse press, Escape cancels.
+/// Runs before the menu buttons so the click that started rebinding isn't captured.
+fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut state: ResMut<State<AppState>>,
+    mut keep_focus: ResMut<KeepMenuFocus>,
+) {
+    let action = match rebinding.0 {
+        Some(action) => action,
+        None => return,
+    };
+
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        });
+    let binding = match pressed {
+        Some(binding) => binding,
+        None => return,
+    };
+
+    if binding != Binding::Key(KeyCode::Escape) {
+        if !bindings.rebind(action, binding) {
+            return;
         }
+        bindings.save();
     }
+
+    rebinding.0 = None;
+    keys.clear();
+    mouse.clear();
+    keep_focus.0 = true;
+    state.restart().unwrap();
 }
 
 fn cleanup_menu(
     mut commands: Commands,
-    button: Query<Entity, With<Button>>,
+    root: Query<Entity, With<Root>>,
     cam: Query<Entity, With<Camera2d>>,
 ) {
-    commands.entity(button.single()).despawn_recursive();
+    commands.entity(root.single()).despawn_recursive();
     commands.entity(cam.single()).despawn_recursive();
 }
 
@@ -109,13 +543,21 @@ pub struct StartMenuPlugin;
 
 impl Plugin for StartMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<ButtonColors>()
+        app.init_resource::<MenuFocus>()
+            .init_resource::<KeepMenuFocus>()
+            .init_resource::<MenuPage>()
+            .init_resource::<Rebinding>()
             .add_system_set(
                 SystemSet::on_enter(AppState::Menu)
-                    .with_system(setup_menu)
-                    .with_system(start_audio),
+                    .with_system(setup_menu.after(reset_menu_navigation))
+                    .with_system(reset_menu_navigation),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Menu)
+                    .with_system(capture_binding.before(click_play_button))
+                    .with_system(click_play_button)
+                    .with_system(navigate_menu.after(capture_binding)),
             )
-            .add_system_set(SystemSet::on_update(AppState::Menu).with_system(click_play_button))
             .add_system_set(SystemSet::on_exit(AppState::Menu).with_system(cleanup_menu));
     }
 }
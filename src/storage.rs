@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Directory used to persist data on native targets.
+#[cfg(not(target_arch = "wasm32"))]
+const STORAGE_DIR: &str = "save";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::Path::new(STORAGE_DIR).join(format!("{}.json", key))
+}
+
+/// Read the raw value stored at `key`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+/// Write a raw `value` at `key`, overwriting any previous value.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(key: &str, value: &str) {
+    let path = path(key);
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(err) = std::fs::write(&path, value) {
+        warn!("failed to write {:?}: {}", path, err);
+    }
+}
+
+/// Remove the value stored at `key`, if any.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(key: &str) {
+    let _ = std::fs::remove_file(path(key));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Read the raw value stored at `key`.
+#[cfg(target_arch = "wasm32")]
+pub fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+/// Write a raw `value` at `key`, overwriting any previous value.
+#[cfg(target_arch = "wasm32")]
+pub fn write(key: &str, value: &str) {
+    if let Some(storage) = local_storage() {
+        if storage.set_item(key, value).is_err() {
+            warn!("failed to write {} to local storage", key);
+        }
+    }
+}
+
+/// Remove the value stored at `key`, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn remove(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
+
+/// Load and deserialize the value stored at `key`. Missing or corrupt values yield `None`.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = read(key)?;
+    match serde_json::from_str(&value) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("ignoring corrupt {}: {}", key, err);
+            None
+        }
+    }
+}
+
+/// Serialize and store `value` at `key`.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(value) => write(key, &value),
+        Err(err) => warn!("failed to serialize {}: {}", key, err),
+    }
+}
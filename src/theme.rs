@@ -0,0 +1,258 @@
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+use serde::{Deserialize, Serialize};
+
+use crate::{gameplay::PLAYER_SPAWN_Z, AppState, ReduceMotion};
+
+#[derive(Debug, Clone)]
+pub struct ButtonColors {
+    pub normal: UiColor,
+    pub hovered: UiColor,
+}
+
+/// Colors used while in a single [AppState].
+#[derive(Debug, Clone)]
+pub struct StateTheme {
+    pub clear_color: Color,
+    pub button: ButtonColors,
+}
+
+/// Per-state colors, applied whenever a state is entered.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub menu: StateTheme,
+    pub gameplay: StateTheme,
+    pub game_over: StateTheme,
+    /// Color of the plane behind the grid during gameplay, `None` leaves the board unbacked.
+    pub board: Option<Color>,
+    /// Gradient drawn behind everything during gameplay, `None` shows the flat clear color.
+    pub background: Option<Gradient>,
+}
+
+/// Colors of a vertical gradient, from the top of the screen to the bottom.
+#[derive(Debug, Clone, Copy)]
+pub struct Gradient {
+    pub top: Color,
+    pub bottom: Color,
+}
+
+impl Theme {
+    fn get(&self, state: &AppState) -> &StateTheme {
+        match state {
+            AppState::Loading | AppState::Menu => &self.menu,
+            AppState::Gameplay => &self.gameplay,
+            AppState::GameOver => &self.game_over,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let neutral = ButtonColors {
+            normal: Color::rgb(0.15, 0.15, 0.15).into(),
+            hovered: Color::rgb(0.25, 0.25, 0.25).into(),
+        };
+        Self {
+            menu: StateTheme {
+                clear_color: Color::rgb(0.1, 0.1, 0.12),
+                button: neutral.clone(),
+            },
+            gameplay: StateTheme {
+                clear_color: Color::rgb(0.1, 0.1, 0.1),
+                button: neutral,
+            },
+            game_over: StateTheme {
+                clear_color: Color::rgb(0.12, 0.08, 0.08),
+                button: ButtonColors {
+                    normal: Color::rgb(1., 0.1, 0.1).into(),
+                    hovered: Color::rgb(1., 0.35, 0.35).into(),
+                },
+            },
+            board: None,
+            background: None,
+        }
+    }
+}
+
+/// Text size preset, scales every font size in the UI.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FontScale {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+impl FontScale {
+    /// Returns `base` scaled by the preset.
+    pub fn size(self, base: f32) -> f32 {
+        base * match self {
+            FontScale::Small => 0.75,
+            FontScale::Medium => 1.0,
+            FontScale::Large => 1.5,
+        }
+    }
+
+    /// Returns the preset after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            FontScale::Small => FontScale::Medium,
+            FontScale::Medium => FontScale::Large,
+            FontScale::Large => FontScale::Small,
+        }
+    }
+
+    /// Localization key of the preset name.
+    pub fn key(self) -> &'static str {
+        match self {
+            FontScale::Small => "small",
+            FontScale::Medium => "medium",
+            FontScale::Large => "large",
+        }
+    }
+}
+
+/// Briefly tints [ClearColor] toward a color, fading back to the theme color.
+#[derive(Debug, Clone)]
+pub struct FlashColor {
+    /// How far the background moves toward the flash color, `0.0..=1.0`.
+    pub intensity: f32,
+    /// Fade duration in seconds.
+    pub duration: f32,
+    /// Minimum number of balls removed in one shot to trigger a flash.
+    pub min_cleared: usize,
+    color: Option<Color>,
+    elapsed: f32,
+}
+
+impl Default for FlashColor {
+    fn default() -> Self {
+        Self {
+            intensity: 0.25,
+            duration: 0.4,
+            min_cleared: 6,
+            color: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl FlashColor {
+    /// Starts a new flash toward `color`, replacing any running one.
+    pub fn trigger(&mut self, color: Color) {
+        self.color = Some(color);
+        self.elapsed = 0.0;
+    }
+
+    fn stop(&mut self) {
+        self.color = None;
+    }
+}
+
+fn apply_clear_color(
+    theme: Res<Theme>,
+    state: Res<State<AppState>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut flash: ResMut<FlashColor>,
+) {
+    clear_color.0 = theme.get(state.current()).clear_color;
+    flash.stop();
+}
+
+fn update_flash(
+    time: Res<Time>,
+    theme: Res<Theme>,
+    reduce_motion: Res<ReduceMotion>,
+    mut flash: ResMut<FlashColor>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let color = match flash.color {
+        Some(color) => color,
+        None => return,
+    };
+
+    let base = theme.gameplay.clear_color;
+    flash.elapsed += time.delta_seconds();
+    if **reduce_motion || flash.elapsed >= flash.duration {
+        flash.stop();
+        clear_color.0 = base;
+        return;
+    }
+
+    let t = (1.0 - flash.elapsed / flash.duration) * flash.intensity.clamp(0.0, 1.0);
+    let from = Vec4::from(base.as_rgba_f32());
+    let to = Vec4::from(color.as_rgba_f32());
+    clear_color.0 = from.lerp(to, t).into();
+}
+
+/// Marks the quad showing [Theme::background].
+#[derive(Component)]
+struct Background;
+
+/// Height of the [Background], below the board.
+const BACKGROUND_Y: f32 = -5.0;
+/// World size of the [Background], large enough to fill the screen.
+const BACKGROUND_SIZE: Vec2 = Vec2::new(300.0, 150.0);
+
+fn setup_background(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    theme: Res<Theme>,
+) {
+    let gradient = match theme.background {
+        Some(gradient) => gradient,
+        None => return,
+    };
+
+    let mut mesh = Mesh::from(shape::Plane { size: 1.0 });
+    let colors = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            let top = Vec4::from(gradient.top.as_linear_rgba_f32());
+            let bottom = Vec4::from(gradient.bottom.as_linear_rgba_f32());
+            positions
+                .iter()
+                .map(|[_, _, z]| top.lerp(bottom, z + 0.5).to_array())
+                .collect::<Vec<_>>()
+        }
+        _ => return,
+    };
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, BACKGROUND_Y, PLAYER_SPAWN_Z / 2.0)
+                .with_scale(Vec3::new(BACKGROUND_SIZE.x, 1.0, BACKGROUND_SIZE.y)),
+            ..default()
+        })
+        .insert(Background);
+}
+
+fn cleanup_background(mut commands: Commands, background: Query<Entity, With<Background>>) {
+    for entity in background.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .init_resource::<FontScale>()
+            .init_resource::<FlashColor>()
+            .add_system_set(SystemSet::on_enter(AppState::Menu).with_system(apply_clear_color))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Gameplay)
+                    .with_system(apply_clear_color)
+                    .with_system(setup_background),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Gameplay).with_system(update_flash))
+            .add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(cleanup_background))
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(apply_clear_color));
+    }
+}
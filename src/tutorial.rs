@@ -0,0 +1,140 @@
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    bindings::KeyBindings, loading::FontAssets, localization::Localization, storage,
+    theme::FontScale, AppState,
+};
+
+const TUTORIAL_KEY: &str = "ball_shooter_tutorial_seen";
+
+#[derive(Component)]
+struct TutorialOverlay;
+
+/// Returns whether the tutorial has been dismissed before.
+pub fn tutorial_seen() -> bool {
+    storage::load::<bool>(TUTORIAL_KEY).unwrap_or(false)
+}
+
+/// Shows the tutorial again the next time gameplay is entered.
+pub fn reset_tutorial() {
+    storage::remove(TUTORIAL_KEY);
+}
+
+/// Stops the tutorial from being shown when gameplay is entered.
+pub fn mark_tutorial_seen() {
+    storage::save(TUTORIAL_KEY, &true);
+}
+
+/// Shows the tutorial the first time gameplay is entered.
+fn show_tutorial_once(
+    commands: Commands,
+    font_assets: Res<FontAssets>,
+    localization: Res<Localization>,
+    font_scale: Res<FontScale>,
+) {
+    if tutorial_seen() {
+        return;
+    }
+
+    spawn_tutorial(commands, &font_assets, &localization, *font_scale);
+}
+
+fn replay_tutorial(
+    commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    overlay: Query<(), With<TutorialOverlay>>,
+    font_assets: Res<FontAssets>,
+    localization: Res<Localization>,
+    font_scale: Res<FontScale>,
+) {
+    if keys.just_pressed(bindings.replay_tutorial) && overlay.is_empty() {
+        spawn_tutorial(commands, &font_assets, &localization, *font_scale);
+    }
+}
+
+fn spawn_tutorial(
+    mut commands: Commands,
+    font_assets: &FontAssets,
+    localization: &Localization,
+    font_scale: FontScale,
+) {
+    let style = TextStyle {
+        font: font_assets.fira_sans.clone(),
+        font_size: font_scale.size(32.0),
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..Default::default()
+        })
+        .insert(TutorialOverlay)
+        .with_children(|parent| {
+            for key in ["tutorial_aim", "tutorial_match", "tutorial_dismiss"] {
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(8.0)),
+                        ..Default::default()
+                    },
+                    text: Text::from_section(localization.get(key), style.clone()),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+/// Hides the tutorial on any click or key press. Runs right after input is processed
+/// so the dismissing input doesn't also fire the projectile, whatever fire is bound to.
+fn dismiss_tutorial(
+    mut commands: Commands,
+    overlay: Query<Entity, With<TutorialOverlay>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut keys: ResMut<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+) {
+    if overlay.is_empty() {
+        return;
+    }
+
+    let clicked = mouse.get_just_pressed().next().is_some();
+    let pressed = keys
+        .get_just_pressed()
+        .any(|key| *key != bindings.replay_tutorial);
+    if !clicked && !pressed {
+        return;
+    }
+
+    mouse.clear();
+    keys.clear();
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    mark_tutorial_seen();
+}
+
+fn cleanup_tutorial(mut commands: Commands, overlay: Query<Entity, With<TutorialOverlay>>) {
+    for entity in overlay.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Gameplay).with_system(show_tutorial_once));
+        app.add_system_set(SystemSet::on_update(AppState::Gameplay).with_system(replay_tutorial));
+        app.add_system_set(SystemSet::on_exit(AppState::Gameplay).with_system(cleanup_tutorial));
+        app.add_system_to_stage(CoreStage::PreUpdate, dismiss_tutorial.after(InputSystem));
+    }
+}